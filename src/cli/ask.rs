@@ -0,0 +1,36 @@
+//! One-shot questions
+
+use crate::cli::output::TerminalSink;
+use skynet_rust::{Config, Message, OutputSink, Result, SkynetAgent, SkynetError};
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+
+/// Answer `prompt`, or stdin when it is empty or `-`, streaming the reply
+pub async fn run(config: Config, prompt: Vec<String>, session: Option<String>) -> Result<()> {
+    let prompt = if prompt.is_empty() || prompt == ["-"] {
+        let mut input = String::new();
+        tokio::io::stdin()
+            .read_to_string(&mut input)
+            .await
+            .map_err(|e| SkynetError::agent("Failed to read from stdin").with_source(e))?;
+        input
+    } else {
+        prompt.join(" ")
+    };
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        return Err(SkynetError::agent("Nothing to ask: the prompt is empty"));
+    }
+
+    let sink = Arc::new(TerminalSink::default());
+    let agent = SkynetAgent::new(config)
+        .await?
+        .with_output(Arc::clone(&sink) as Arc<dyn OutputSink>);
+    let session = session.unwrap_or_else(|| agent.session_id().to_string());
+
+    let result = agent
+        .respond(Message::user(prompt.to_string()).with_session(&session))
+        .await;
+    sink.finish_line();
+    result.map(|_| ())
+}
@@ -0,0 +1,257 @@
+//! Interactive chat with the agent in the terminal
+
+use crate::cli::output::{flush_stdout, TerminalSink};
+use skynet_rust::core::usage::UsageSummary;
+use skynet_rust::{Config, Message, OutputSink, Result, SessionManager, SkynetAgent, SkynetError};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::watch;
+use tracing::warn;
+
+const HELP: &str = "\
+Commands:
+  /reset                   start a new, empty session
+  /session                 show the current session
+  /session list            list sessions
+  /session new [title]     start a new session with a title
+  /session switch <id>     continue another session
+  /session fork [title]    copy the current session and switch to the copy
+  /model [name|default]    show or change the model
+  /tools                   list the tools the agent may call
+  /cost                    show token usage and cost
+  /save [path]             write the session history to a JSON file
+  /help                    show this help
+  /quit                    leave the chat";
+
+/// What the REPL does after a command
+enum Flow {
+    Continue,
+    Quit,
+}
+
+/// REPL state: the agent and the session being talked to
+struct Chat {
+    agent: SkynetAgent,
+    sink: Arc<TerminalSink>,
+    sessions: SessionManager,
+    session: String,
+}
+
+/// Run the chat until `/quit` or end of input, starting in `session` or
+/// the default session. Configuration `updates` are applied between turns.
+pub async fn run(
+    config: Config,
+    mut updates: watch::Receiver<Arc<Config>>,
+    session: Option<String>,
+) -> Result<()> {
+    let sink = Arc::new(TerminalSink::default());
+    let agent = SkynetAgent::new(config)
+        .await?
+        .with_output(Arc::clone(&sink) as Arc<dyn OutputSink>);
+    let sessions = agent.sessions();
+    let session = match session {
+        Some(id) => sessions.require(&id).await?.id,
+        None => agent.session_id().to_string(),
+    };
+
+    let mut chat = Chat {
+        agent,
+        sink,
+        sessions,
+        session,
+    };
+    println!(
+        "SKYNET chat: model {}, session {}. Type /help for commands.",
+        chat.agent.model(),
+        chat.session
+    );
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        print!("> ");
+        flush_stdout()?;
+
+        let line = loop {
+            tokio::select! {
+                line = lines.next_line() => break line,
+                Ok(()) = updates.changed() => {
+                    let config = Arc::clone(&updates.borrow_and_update());
+                    if let Err(e) = chat.agent.apply_config(&config) {
+                        warn!("Keeping the previous settings: {}", e);
+                    }
+                }
+            }
+        };
+        let Some(line) =
+            line.map_err(|e| SkynetError::agent("Failed to read from stdin").with_source(e))?
+        else {
+            println!();
+            break;
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(command) = line.strip_prefix('/') {
+            match chat.command(command).await {
+                Ok(Flow::Quit) => break,
+                Ok(Flow::Continue) => {}
+                Err(e) => eprintln!("error: {}", e),
+            }
+            continue;
+        }
+
+        let input = Message::user(line.to_string()).with_session(&chat.session);
+        if let Err(e) = chat.agent.respond(input).await {
+            chat.sink.finish_line();
+            eprintln!("error: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+impl Chat {
+    async fn command(&mut self, command: &str) -> Result<Flow> {
+        let (name, args) = command
+            .split_once(char::is_whitespace)
+            .map(|(name, args)| (name, args.trim()))
+            .unwrap_or((command, ""));
+
+        match name {
+            "help" => println!("{}", HELP),
+            "quit" | "exit" => return Ok(Flow::Quit),
+            "reset" => {
+                let session = self.sessions.create(None).await?;
+                self.switch(session.id);
+            }
+            "session" => self.session(args).await?,
+            "model" => self.model(args),
+            "tools" => self.tools(),
+            "cost" => self.cost(),
+            "save" => self.save(args).await?,
+            other => eprintln!("Unknown command /{}; type /help for commands", other),
+        }
+        Ok(Flow::Continue)
+    }
+
+    async fn session(&mut self, args: &str) -> Result<()> {
+        let (action, rest) = args
+            .split_once(char::is_whitespace)
+            .map(|(action, rest)| (action, rest.trim()))
+            .unwrap_or((args, ""));
+        let title = (!rest.is_empty()).then(|| rest.to_string());
+
+        match action {
+            "" => {
+                let session = self.sessions.require(&self.session).await?;
+                let messages = self.sessions.history(&session.id).await?.len();
+                println!(
+                    "Session {}{} ({} messages, created {})",
+                    session.id,
+                    session
+                        .title
+                        .map(|title| format!(" \"{}\"", title))
+                        .unwrap_or_default(),
+                    messages,
+                    session.created_at.format("%Y-%m-%d %H:%M")
+                );
+            }
+            "list" => {
+                for session in self.sessions.list(true).await? {
+                    println!(
+                        "{} {}  {}  {}{}",
+                        if session.id == self.session { "*" } else { " " },
+                        session.id,
+                        session.updated_at.format("%Y-%m-%d %H:%M"),
+                        session.title.as_deref().unwrap_or("(untitled)"),
+                        if session.archived { "  [archived]" } else { "" }
+                    );
+                }
+            }
+            "new" => {
+                let session = self.sessions.create(title).await?;
+                self.switch(session.id);
+            }
+            "switch" if !rest.is_empty() => {
+                let session = self.sessions.require(rest).await?;
+                self.switch(session.id);
+            }
+            "fork" => {
+                let session = self.sessions.fork(&self.session, title).await?;
+                self.switch(session.id);
+            }
+            _ => eprintln!("Usage: /session [list | new [title] | switch <id> | fork [title]]"),
+        }
+        Ok(())
+    }
+
+    fn switch(&mut self, session: String) {
+        println!("Now in session {}", session);
+        self.session = session;
+    }
+
+    fn model(&mut self, args: &str) {
+        match args {
+            "" => println!("Model: {}", self.agent.model()),
+            "default" => {
+                self.agent.set_model(None);
+                println!("Model: {}", self.agent.model());
+            }
+            model => {
+                self.agent.set_model(Some(model.to_string()));
+                println!("Model: {}", self.agent.model());
+            }
+        }
+    }
+
+    fn tools(&self) {
+        let tools = self.agent.tools();
+        if tools.is_empty() {
+            println!("No tools registered");
+        }
+        for tool in tools {
+            println!("{}: {}", tool.name, tool.description);
+        }
+    }
+
+    fn cost(&self) {
+        let report = self.agent.usage_report();
+        print_usage("This session", report.by_session.get(&self.session));
+        print_usage("All sessions", Some(&report.total));
+        for (provider, summary) in &report.by_provider {
+            print_usage(&format!("  {}", provider), Some(summary));
+        }
+    }
+
+    async fn save(&self, path: &str) -> Result<()> {
+        let path = if path.is_empty() {
+            format!("skynet-{}.json", self.session)
+        } else {
+            path.to_string()
+        };
+        let history = self.sessions.history(&self.session).await?;
+        let json = serde_json::to_string_pretty(&history)
+            .map_err(|e| SkynetError::agent("Failed to encode history").with_source(e))?;
+        tokio::fs::write(&path, json)
+            .await
+            .map_err(|e| SkynetError::agent(format!("Failed to write {}", path)).with_source(e))?;
+        println!("Saved {} messages to {}", history.len(), path);
+        Ok(())
+    }
+}
+
+fn print_usage(label: &str, summary: Option<&UsageSummary>) {
+    let summary = summary.cloned().unwrap_or_default();
+    println!(
+        "{}: {} requests, {} input / {} output tokens, ${:.6}",
+        label,
+        summary.requests,
+        summary.usage.input_tokens
+            + summary.usage.cache_creation_input_tokens
+            + summary.usage.cache_read_input_tokens,
+        summary.usage.output_tokens,
+        summary.cost_usd
+    );
+}
@@ -0,0 +1,100 @@
+//! `skynet config` subcommands
+
+use clap::Subcommand;
+use skynet_rust::config::{ConfigLoader, ConfigReport};
+use skynet_rust::providers::ProviderRegistry;
+use skynet_rust::{Config, Result, SkynetError};
+use std::path::Path;
+
+/// Default location of the configuration file
+const DEFAULT_PATH: &str = "skynet.toml";
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Write a default configuration file
+    Init {
+        /// Where to write it; defaults to --config or ./skynet.toml
+        path: Option<String>,
+
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print the effective configuration, with API keys redacted
+    Show {
+        /// List every value with the layer it came from instead
+        #[arg(long)]
+        origins: bool,
+    },
+
+    /// Check that the configuration loads and its providers can be built
+    Validate,
+}
+
+pub async fn execute(
+    command: ConfigCommand,
+    loader: &ConfigLoader,
+    config_path: Option<&str>,
+) -> Result<()> {
+    match command {
+        ConfigCommand::Init { path, force } => {
+            let path = path.as_deref().or(config_path).unwrap_or(DEFAULT_PATH);
+            init(path, force).await
+        }
+        ConfigCommand::Show { origins } => {
+            let (config, report) = loader.load_with_report()?;
+            for issue in report.issues() {
+                eprintln!("warning: showing the default instead of {}", issue);
+            }
+            if origins {
+                show_origins(&config, &report)
+            } else {
+                show(&config)
+            }
+        }
+        ConfigCommand::Validate => validate(&loader.load()?),
+    }
+}
+
+/// Write the default configuration to `path`
+pub async fn init(path: &str, force: bool) -> Result<()> {
+    if !force && Path::new(path).exists() {
+        return Err(SkynetError::config(format!(
+            "{} already exists; pass --force to overwrite it",
+            path
+        )));
+    }
+    Config::create_default_config(path).await?;
+    println!("Wrote default configuration to {}", path);
+    println!("The API key is read from ANTHROPIC_API_KEY; change anthropic_api_key to use another source.");
+    Ok(())
+}
+
+fn show(config: &Config) -> Result<()> {
+    let toml = toml::to_string_pretty(config)
+        .map_err(|e| SkynetError::config("Failed to serialize configuration").with_source(e))?;
+    print!("{}", toml);
+    Ok(())
+}
+
+fn show_origins(config: &Config, report: &ConfigReport) -> Result<()> {
+    for file in report.files() {
+        let status = if file.loaded { "loaded" } else { "not found" };
+        println!("# {}: {} ({})", file.layer, file.path.display(), status);
+    }
+    for (key, value, origin) in report.entries(config)? {
+        println!("{} = {}  # {}", key, value, origin);
+    }
+    Ok(())
+}
+
+fn validate(config: &Config) -> Result<()> {
+    ProviderRegistry::default().create_from_config(config)?;
+    println!(
+        "Configuration is valid (provider {}, model {})",
+        config.active_provider_name(),
+        config.active_provider()?.model
+    );
+    Ok(())
+}
@@ -0,0 +1,368 @@
+//! Log output of the `skynet` binary
+
+use skynet_rust::config::{LogFormat, LogRotation, LoggingConfig};
+use skynet_rust::{Result, SkynetError};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::field::MakeVisitor;
+use tracing_subscriber::fmt::format::{DefaultFields, Format, Writer};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::fmt::{self, FormatFields, MakeWriter};
+use tracing_subscriber::layer::Layered;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+
+/// Subscriber the output layers write for
+type Filtered = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+
+/// Layers writing log lines, one per destination
+type Output = Vec<Box<dyn Layer<Filtered> + Send + Sync>>;
+
+/// Adjusts the log level and destinations of the installed subscriber after
+/// start-up
+#[derive(Clone)]
+pub struct Logging {
+    filter: reload::Handle<EnvFilter, Registry>,
+    output: reload::Handle<Output, Filtered>,
+    /// `RUST_LOG` is set and takes precedence over the configuration
+    from_env: bool,
+}
+
+impl Logging {
+    /// Log text to stderr, keeping stdout for replies; `RUST_LOG` applies
+    /// until a configuration is loaded, and overrides it when set
+    pub fn init() -> Self {
+        let from_env = std::env::var_os(EnvFilter::DEFAULT_ENV).is_some();
+        let (filter, filter_handle) = reload::Layer::new(EnvFilter::from_default_env());
+        let (output, output_handle) = reload::Layer::new(stderr(LogFormat::Text));
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(output)
+            .init();
+        Self {
+            filter: filter_handle,
+            output: output_handle,
+            from_env,
+        }
+    }
+
+    /// Log at the configured level and in the configured format, to the log
+    /// file as well when `file_logging` is set
+    pub fn apply(&self, logging: &LoggingConfig) -> Result<()> {
+        self.set_level(logging);
+
+        let mut output = stderr(logging.format);
+        if let (true, Some(path)) = (logging.file_logging, &logging.log_file) {
+            let layer = fmt::layer()
+                .fmt_fields(PlainFields::default())
+                .with_ansi(false)
+                .with_writer(log_file(path, logging)?);
+            output.push(formatted(layer, logging.format));
+        }
+        self.output
+            .reload(output)
+            .map_err(|e| SkynetError::config("Failed to change the log output").with_source(e))
+    }
+
+    /// Warn that log output set up from `in_effect` stays as it is although
+    /// `configured` changes it: destinations and formats need a restart
+    pub fn warn_if_output_changed(&self, in_effect: &LoggingConfig, configured: &LoggingConfig) {
+        let output = |logging: &LoggingConfig| {
+            (
+                logging
+                    .file_logging
+                    .then(|| logging.log_file.clone())
+                    .flatten(),
+                logging.format,
+                logging.rotation,
+                logging.max_size_bytes,
+                logging.max_log_files,
+            )
+        };
+        if output(in_effect) == output(configured) {
+            return;
+        }
+        let destination = match (in_effect.file_logging, &in_effect.log_file) {
+            (true, Some(path)) => format!("stderr and {}", path),
+            _ => "stderr".to_string(),
+        };
+        warn!(
+            "Log output settings changed; logs go to {} until restart",
+            destination
+        );
+    }
+
+    /// Log at the configured level, unless `RUST_LOG` is set
+    pub fn set_level(&self, logging: &LoggingConfig) {
+        if self.from_env {
+            return;
+        }
+        if let Err(e) = self.filter.reload(EnvFilter::new(&logging.level)) {
+            warn!("Failed to change the log level: {}", e);
+        }
+    }
+}
+
+fn stderr(format: LogFormat) -> Output {
+    vec![formatted(fmt::layer().with_writer(std::io::stderr), format)]
+}
+
+fn formatted<N, W>(
+    layer: fmt::Layer<Filtered, N, Format, W>,
+    format: LogFormat,
+) -> Box<dyn Layer<Filtered> + Send + Sync>
+where
+    N: for<'w> FormatFields<'w> + Send + Sync + 'static,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+/// Span fields are formatted once per formatter type and shared between
+/// layers, so the log file needs its own type to keep stderr's colors out
+#[derive(Default)]
+struct PlainFields(DefaultFields);
+
+impl<'w> MakeVisitor<Writer<'w>> for PlainFields {
+    type Visitor = <DefaultFields as MakeVisitor<Writer<'w>>>::Visitor;
+
+    fn make_visitor(&self, target: Writer<'w>) -> Self::Visitor {
+        self.0.make_visitor(target)
+    }
+}
+
+/// Appender writing to `path`, rotated and pruned as configured
+fn log_file(path: &str, logging: &LoggingConfig) -> Result<BoxMakeWriter> {
+    let open_failed =
+        |e| SkynetError::config(format!("Failed to open log file {}", path)).with_source(e);
+    if let Some(max_size) = logging.max_size_bytes {
+        let file = SizeRotatingFile::open(Path::new(path), max_size, logging.max_log_files)
+            .map_err(open_failed)?;
+        return Ok(BoxMakeWriter::new(Mutex::new(file)));
+    }
+
+    let path = Path::new(path);
+    let name = path.file_name().ok_or_else(|| {
+        SkynetError::config(format!("Log file {} is not a file name", path.display()))
+    })?;
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let rotation = match logging.rotation {
+        LogRotation::Never => Rotation::NEVER,
+        LogRotation::Minutely => Rotation::MINUTELY,
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+    };
+    let mut builder = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(name.to_string_lossy());
+    if let Some(max_files) = logging.max_log_files {
+        builder = builder.max_log_files(max_files);
+    }
+    let appender = builder.build(dir).map_err(|e| {
+        SkynetError::config(format!("Failed to open log file {}", path.display())).with_source(e)
+    })?;
+    Ok(BoxMakeWriter::new(appender))
+}
+
+/// Log file started afresh once a write would take it past `max_size`
+/// bytes. Earlier files are kept as `<path>.1` (the newest), `<path>.2` and
+/// so on, up to `max_files` files in all.
+struct SizeRotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: Option<usize>,
+}
+
+impl SizeRotatingFile {
+    fn open(path: &Path, max_size: u64, max_files: Option<usize>) -> io::Result<Self> {
+        let file = append(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size,
+            max_size,
+            max_files,
+        })
+    }
+
+    /// Path of the `index`th most recent rolled-over file
+    fn rolled(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    /// Move the current file to `<path>.1`, shifting older ones up and
+    /// deleting those beyond `max_files`, and start a new one
+    fn roll_over(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        // Rolled-over files to keep next to the new current file
+        let keep = self.max_files.map(|max| max.saturating_sub(1));
+        let mut oldest = 0;
+        while self.rolled(oldest + 1).exists() {
+            oldest += 1;
+        }
+        for index in (1..=oldest).rev() {
+            match keep {
+                Some(keep) if index >= keep => fs::remove_file(self.rolled(index))?,
+                _ => fs::rename(self.rolled(index), self.rolled(index + 1))?,
+            }
+        }
+        match keep {
+            Some(0) => fs::remove_file(&self.path)?,
+            _ => fs::rename(&self.path, self.rolled(1))?,
+        }
+        self.file = append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for SizeRotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // A line longer than `max_size` gets a file of its own
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.roll_over()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory under the system temp directory, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "skynet-logging-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        /// Names of the files in the directory, sorted
+        fn files(&self) -> Vec<String> {
+            let mut files: Vec<String> = fs::read_dir(&self.0)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            files.sort();
+            files
+        }
+
+        fn read(&self, name: &str) -> String {
+            fs::read_to_string(self.0.join(name)).unwrap()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write_lines(file: &mut SizeRotatingFile, lines: &[&str]) {
+        for line in lines {
+            file.write_all(format!("{}\n", line).as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+    }
+
+    #[test]
+    fn rolls_over_once_the_size_limit_is_reached() {
+        let dir = TempDir::new("size");
+        let path = dir.0.join("skynet.log");
+        let mut file = SizeRotatingFile::open(&path, 10, None).unwrap();
+
+        write_lines(&mut file, &["one", "two", "three", "four"]);
+        assert_eq!(dir.files(), ["skynet.log", "skynet.log.1", "skynet.log.2"]);
+        assert_eq!(dir.read("skynet.log.2"), "one\ntwo\n");
+        assert_eq!(dir.read("skynet.log.1"), "three\n");
+        assert_eq!(dir.read("skynet.log"), "four\n");
+    }
+
+    #[test]
+    fn keeps_at_most_max_log_files() {
+        let dir = TempDir::new("retention");
+        let path = dir.0.join("skynet.log");
+        let mut file = SizeRotatingFile::open(&path, 4, Some(3)).unwrap();
+
+        write_lines(&mut file, &["one", "two", "three", "four", "five"]);
+        assert_eq!(dir.files(), ["skynet.log", "skynet.log.1", "skynet.log.2"]);
+        assert_eq!(dir.read("skynet.log.2"), "three\n");
+        assert_eq!(dir.read("skynet.log.1"), "four\n");
+        assert_eq!(dir.read("skynet.log"), "five\n");
+    }
+
+    #[test]
+    fn continues_an_existing_file_and_counts_its_size() {
+        let dir = TempDir::new("reopen");
+        let path = dir.0.join("skynet.log");
+        fs::write(&path, "earlier\n").unwrap();
+        let mut file = SizeRotatingFile::open(&path, 13, Some(1)).unwrap();
+
+        write_lines(&mut file, &["next"]);
+        assert_eq!(dir.read("skynet.log"), "earlier\nnext\n");
+        write_lines(&mut file, &["last"]);
+        assert_eq!(dir.files(), ["skynet.log"]);
+        assert_eq!(dir.read("skynet.log"), "last\n");
+    }
+
+    #[test]
+    fn time_rotation_keeps_max_log_files() {
+        let dir = TempDir::new("time");
+        // Pruning goes by creation time, which the pauses keep apart
+        for day in ["2026-10-13", "2026-10-14", "2026-10-15"] {
+            fs::write(dir.0.join(format!("skynet.log.{}", day)), day).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let logging = LoggingConfig {
+            rotation: LogRotation::Daily,
+            max_log_files: Some(2),
+            ..LoggingConfig::default()
+        };
+        let path = dir.0.join("skynet.log");
+        let writer = log_file(path.to_str().unwrap(), &logging).unwrap();
+        writer.make_writer().write_all(b"today\n").unwrap();
+
+        let today = chrono::Utc::now().format("%Y-%m-%d");
+        assert_eq!(
+            dir.files(),
+            [
+                "skynet.log.2026-10-15".to_string(),
+                format!("skynet.log.{}", today)
+            ]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! `skynet memory` subcommands
+
+use clap::Subcommand;
+use serde::Serialize;
+use skynet_rust::memory::{Memory, SearchQuery};
+use skynet_rust::{Config, Message, Result, Role, Session, SessionManager, SkynetError};
+use std::sync::Arc;
+
+#[derive(Debug, Subcommand)]
+pub enum MemoryCommand {
+    /// Full-text search over stored messages
+    Search {
+        /// Words, "quoted phrases" and prefix* terms, all of which must match
+        query: String,
+
+        /// Only search this session
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Only return messages with this role (repeatable)
+        #[arg(long, value_parser = parse_role)]
+        role: Vec<Role>,
+
+        /// Maximum number of results
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Write sessions and all their messages as JSON
+    Export {
+        /// Only export this session
+        #[arg(long)]
+        session: Option<String>,
+
+        /// File to write; defaults to stdout
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+
+    /// Delete messages older than a number of days
+    Prune {
+        /// Delete messages older than this many days
+        #[arg(long, value_name = "DAYS")]
+        older_than: u32,
+
+        /// Only prune this session
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Report what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// A session and its messages, oldest first, as exported
+#[derive(Serialize)]
+struct SessionExport {
+    session: Session,
+    messages: Vec<Message>,
+}
+
+pub async fn execute(command: MemoryCommand, config: Config) -> Result<()> {
+    let memory = skynet_rust::memory::from_config(&config.database)?;
+    let sessions = SessionManager::new(Arc::clone(&memory));
+
+    match command {
+        MemoryCommand::Search {
+            query,
+            session,
+            role,
+            limit,
+        } => {
+            let mut query = SearchQuery::new(query).with_roles(role).with_limit(limit);
+            if let Some(session) = &session {
+                query = query.in_session(session);
+            }
+            let hits = memory.search_with(&query).await?;
+            if hits.is_empty() {
+                println!("No matches");
+            }
+            for hit in hits {
+                println!(
+                    "{}  {}  {:?}: {}",
+                    hit.message.timestamp.format("%Y-%m-%d %H:%M"),
+                    hit.message.session(),
+                    hit.message.role,
+                    hit.snippet.replace('\n', " ")
+                );
+            }
+        }
+        MemoryCommand::Export { session, output } => {
+            let selected = match &session {
+                Some(id) => vec![sessions.require(id).await?],
+                None => sessions.list(true).await?,
+            };
+            let mut export = Vec::with_capacity(selected.len());
+            for session in selected {
+                let messages = all_messages(memory.as_ref(), &session.id).await?;
+                export.push(SessionExport { session, messages });
+            }
+
+            let json = serde_json::to_string_pretty(&export)
+                .map_err(|e| SkynetError::memory("Failed to encode export").with_source(e))?;
+            match output {
+                Some(path) => {
+                    tokio::fs::write(&path, json).await.map_err(|e| {
+                        SkynetError::memory(format!("Failed to write {}", path)).with_source(e)
+                    })?;
+                    eprintln!("Exported {} sessions to {}", export.len(), path);
+                }
+                None => println!("{}", json),
+            }
+        }
+        MemoryCommand::Prune {
+            older_than,
+            session,
+            dry_run,
+        } => {
+            if let Some(id) = &session {
+                sessions.require(id).await?;
+            }
+            let before = chrono::Utc::now() - chrono::Duration::days(older_than.into());
+            if dry_run {
+                let mut query = SearchQuery::new("").between(None, Some(before));
+                if let Some(session) = &session {
+                    query = query.in_session(session);
+                }
+                let count = memory.search_with(&query).await?.len();
+                println!("Would delete {} messages from before {}", count, before);
+            } else {
+                let count = memory.prune(before, session.as_deref()).await?;
+                println!("Deleted {} messages from before {}", count, before);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Every message of a session, including ones compacted into a summary
+async fn all_messages(memory: &dyn Memory, session_id: &str) -> Result<Vec<Message>> {
+    let mut messages: Vec<Message> = memory
+        .search_with(&SearchQuery::new("").in_session(session_id))
+        .await?
+        .into_iter()
+        .map(|hit| hit.message)
+        .collect();
+    messages.sort_by_key(|message| message.timestamp);
+    Ok(messages)
+}
+
+fn parse_role(role: &str) -> std::result::Result<Role, String> {
+    match role.to_lowercase().as_str() {
+        "system" => Ok(Role::System),
+        "user" => Ok(Role::User),
+        "assistant" => Ok(Role::Assistant),
+        "tool" => Ok(Role::Tool),
+        other => Err(format!(
+            "unknown role '{}' (expected system, user, assistant or tool)",
+            other
+        )),
+    }
+}
@@ -0,0 +1,299 @@
+//! Command-line front ends of the `skynet` binary
+
+pub mod ask;
+pub mod chat;
+pub mod config;
+pub mod logging;
+pub mod memory;
+pub mod output;
+pub mod run;
+pub mod tools;
+
+use clap::{Parser, Subcommand};
+use logging::Logging;
+use skynet_rust::config::{ConfigLoader, ConfigWatcher};
+use skynet_rust::{Config, Result};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// SKYNET - ultra-lightweight AI agent framework
+#[derive(Debug, Parser)]
+#[command(name = "skynet", version, about)]
+pub struct Cli {
+    /// Configuration file to use instead of ./skynet.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<String>,
+
+    /// Override a configuration value, e.g. `--set agent.temperature=0.2`
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE", value_parser = parse_override)]
+    pub overrides: Vec<(String, String)>,
+
+    /// Write a default configuration file and exit (same as `config init`)
+    #[arg(long, value_name = "PATH", hide = true)]
+    pub create_config: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the agent loop, answering messages read from stdin (default)
+    Run,
+
+    /// Chat with the agent interactively
+    Chat {
+        /// Session to continue instead of the default one
+        #[arg(long)]
+        session: Option<String>,
+    },
+
+    /// Ask a single question and print the answer
+    Ask {
+        /// Prompt; read from stdin when omitted or `-`
+        prompt: Vec<String>,
+
+        /// Session to ask in instead of the default one
+        #[arg(long)]
+        session: Option<String>,
+    },
+
+    /// Create, show or validate the configuration
+    #[command(subcommand)]
+    Config(config::ConfigCommand),
+
+    /// Search, export or prune stored messages
+    #[command(subcommand)]
+    Memory(memory::MemoryCommand),
+
+    /// Inspect the tools available to the agent
+    #[command(subcommand)]
+    Tools(tools::ToolsCommand),
+}
+
+impl Cli {
+    /// Run the selected command
+    pub async fn execute(self, logging: &Logging) -> Result<()> {
+        if let Some(path) = &self.create_config {
+            return config::init(path, false).await;
+        }
+
+        let loader = self.loader();
+        let load = || -> Result<Config> {
+            let config = loader.load()?;
+            logging.apply(&config.logging)?;
+            Ok(config)
+        };
+        match self.command.unwrap_or(Command::Run) {
+            Command::Run => {
+                let config = load()?;
+                let (_watcher, updates) = watch_config(loader, &config, logging)?;
+                run::run(config, updates).await
+            }
+            Command::Chat { session } => {
+                let config = load()?;
+                let (_watcher, updates) = watch_config(loader, &config, logging)?;
+                chat::run(config, updates, session).await
+            }
+            Command::Ask { prompt, session } => ask::run(load()?, prompt, session).await,
+            Command::Config(command) => {
+                config::execute(command, &loader, self.config.as_deref()).await
+            }
+            Command::Memory(command) => memory::execute(command, load()?).await,
+            Command::Tools(command) => tools::execute(command, load()?).await,
+        }
+    }
+
+    /// Loader for the standard layers, with `--config` as the project file
+    /// and `--set` overrides on top
+    fn loader(&self) -> ConfigLoader {
+        let mut loader = ConfigLoader::new();
+        if let Some(path) = &self.config {
+            loader = loader.project_file(path);
+        }
+        for (key, value) in &self.overrides {
+            loader = loader.set(key, value);
+        }
+        loader
+    }
+}
+
+/// Watch the configuration files for changes, keeping the log level up to
+/// date and warning about log output changes that need a restart. Watching
+/// stops when the returned watcher is dropped.
+fn watch_config(
+    loader: ConfigLoader,
+    config: &Config,
+    logging: &Logging,
+) -> Result<(ConfigWatcher, watch::Receiver<Arc<Config>>)> {
+    let (watcher, updates) = ConfigWatcher::spawn(loader, config.clone())?;
+
+    let mut log_updates = updates.clone();
+    let logging = logging.clone();
+    let in_effect = config.logging.clone();
+    tokio::spawn(async move {
+        while log_updates.changed().await.is_ok() {
+            let config = Arc::clone(&log_updates.borrow_and_update());
+            logging.set_level(&config.logging);
+            logging.warn_if_output_changed(&in_effect, &config.logging);
+        }
+    });
+    Ok((watcher, updates))
+}
+
+fn parse_override(arg: &str) -> std::result::Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected KEY=VALUE, got '{}'", arg)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("skynet").chain(args.iter().copied())).unwrap()
+    }
+
+    fn parse_error(args: &[&str]) -> ErrorKind {
+        Cli::try_parse_from(std::iter::once("skynet").chain(args.iter().copied()))
+            .unwrap_err()
+            .kind()
+    }
+
+    #[test]
+    fn runs_the_agent_loop_without_a_command() {
+        let cli = parse(&[]);
+        assert!(cli.command.is_none());
+        assert!(cli.config.is_none() && cli.create_config.is_none());
+        assert!(matches!(parse(&["run"]).command, Some(Command::Run)));
+    }
+
+    #[test]
+    fn global_options_go_before_or_after_the_command() {
+        for args in [
+            &[
+                "--config",
+                "a.toml",
+                "--set",
+                "agent.temperature=0.2",
+                "run",
+            ][..],
+            &[
+                "run",
+                "--config",
+                "a.toml",
+                "--set",
+                "agent.temperature=0.2",
+            ][..],
+        ] {
+            let cli = parse(args);
+            assert_eq!(cli.config.as_deref(), Some("a.toml"));
+            assert_eq!(
+                cli.overrides,
+                [("agent.temperature".to_string(), "0.2".to_string())]
+            );
+        }
+    }
+
+    #[test]
+    fn set_keeps_everything_after_the_first_equals_sign() {
+        let cli = parse(&["--set", " provider =a=b", "--set", "default_model="]);
+        assert_eq!(
+            cli.overrides,
+            [
+                ("provider".to_string(), "a=b".to_string()),
+                ("default_model".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(
+            parse_error(&["--set", "provider"]),
+            ErrorKind::ValueValidation
+        );
+        assert_eq!(parse_error(&["--set", "=x"]), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn create_config_takes_a_path() {
+        let cli = parse(&["--create-config", "new.toml"]);
+        assert_eq!(cli.create_config.as_deref(), Some("new.toml"));
+        assert_eq!(parse_error(&["--create-config"]), ErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn config_init_takes_an_optional_path_and_force() {
+        match parse(&["config", "init"]).command {
+            Some(Command::Config(config::ConfigCommand::Init { path, force })) => {
+                assert_eq!(path, None);
+                assert!(!force);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        match parse(&["config", "init", "x.toml", "--force"]).command {
+            Some(Command::Config(config::ConfigCommand::Init { path, force })) => {
+                assert_eq!(path.as_deref(), Some("x.toml"));
+                assert!(force);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert_eq!(
+            parse_error(&["config"]),
+            ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+        );
+    }
+
+    #[test]
+    fn ask_joins_words_and_leaves_stdin_to_the_command() {
+        match parse(&["ask", "--session", "s1", "what", "is", "up"]).command {
+            Some(Command::Ask { prompt, session }) => {
+                assert_eq!(prompt, ["what", "is", "up"]);
+                assert_eq!(session.as_deref(), Some("s1"));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        for args in [&["ask"][..], &["ask", "-"][..]] {
+            match parse(args).command {
+                Some(Command::Ask { prompt, session }) => {
+                    assert!(prompt.is_empty() || prompt == ["-"]);
+                    assert_eq!(session, None);
+                }
+                other => panic!("unexpected command: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn memory_export_and_prune_options() {
+        match parse(&["memory", "export", "--session", "s1", "-o", "out.json"]).command {
+            Some(Command::Memory(memory::MemoryCommand::Export { session, output })) => {
+                assert_eq!(session.as_deref(), Some("s1"));
+                assert_eq!(output.as_deref(), Some("out.json"));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        match parse(&["memory", "prune", "--older-than", "30", "--dry-run"]).command {
+            Some(Command::Memory(memory::MemoryCommand::Prune {
+                older_than,
+                session,
+                dry_run,
+            })) => {
+                assert_eq!(older_than, 30);
+                assert_eq!(session, None);
+                assert!(dry_run);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert_eq!(
+            parse_error(&["memory", "prune"]),
+            ErrorKind::MissingRequiredArgument
+        );
+        assert_eq!(
+            parse_error(&["memory", "prune", "--older-than", "-1"]),
+            ErrorKind::UnknownArgument
+        );
+    }
+}
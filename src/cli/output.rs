@@ -0,0 +1,67 @@
+//! Output sinks that print the agent's replies
+
+use async_trait::async_trait;
+use skynet_rust::{Message, MessageType, OutputSink, Result, SkynetError};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Prints each finished reply on its own line, errors to stderr
+pub struct StdoutSink;
+
+#[async_trait]
+impl OutputSink for StdoutSink {
+    async fn deliver(&self, reply: &Message) -> Result<()> {
+        match reply.message_type {
+            MessageType::Error => eprintln!("error: {}", reply.content),
+            _ => println!("{}", reply.content),
+        }
+        Ok(())
+    }
+}
+
+/// Prints replies as they stream in; for one conversation at a time
+#[derive(Default)]
+pub struct TerminalSink {
+    /// Whether part of a reply has been printed without a newline
+    streaming: AtomicBool,
+}
+
+impl TerminalSink {
+    /// End a partially printed reply, e.g. after the turn failed
+    pub fn finish_line(&self) {
+        if self.streaming.swap(false, Ordering::SeqCst) {
+            println!();
+        }
+    }
+}
+
+#[async_trait]
+impl OutputSink for TerminalSink {
+    async fn deliver(&self, reply: &Message) -> Result<()> {
+        if self.streaming.swap(false, Ordering::SeqCst) {
+            println!();
+        } else if reply.message_type == MessageType::Error {
+            eprintln!("error: {}", reply.content);
+        } else {
+            println!("{}", reply.content);
+        }
+        Ok(())
+    }
+
+    fn streams(&self) -> bool {
+        true
+    }
+
+    async fn delta(&self, _input: &Message, text: &str) -> Result<()> {
+        self.streaming.store(true, Ordering::SeqCst);
+        print!("{}", text);
+        flush_stdout()
+    }
+}
+
+/// Flush stdout after printing a partial line
+pub fn flush_stdout() -> Result<()> {
+    std::io::stdout()
+        .flush()
+        .map_err(|e| SkynetError::agent("Failed to write to stdout").with_source(e))
+}
@@ -0,0 +1,73 @@
+//! The agent loop, fed from stdin
+
+use crate::cli::output::StdoutSink;
+use skynet_rust::skynet::Pulse;
+use skynet_rust::{Config, Message, Result, SkynetAgent};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// Answer each line of stdin as a message until end of input, applying
+/// configuration `updates` to the agent and its pulse as they arrive
+pub async fn run(config: Config, updates: watch::Receiver<Arc<Config>>) -> Result<()> {
+    let pulse = Arc::new(Pulse::new(heartbeat_interval(&config)));
+
+    // Create and initialize the agent
+    let mut agent = SkynetAgent::new(config)
+        .await?
+        .with_output(Arc::new(StdoutSink))
+        .with_config_updates(updates.clone());
+    info!("SKYNET agent initialized");
+
+    // Monitor the agent's heartbeat at the configured interval
+    let monitor = tokio::spawn({
+        let pulse = Arc::clone(&pulse);
+        async move {
+            if let Err(e) = pulse.run().await {
+                warn!("Pulse monitor failed: {}", e);
+            }
+        }
+    });
+    let pulse_updates = tokio::spawn({
+        let pulse = Arc::clone(&pulse);
+        let mut updates = updates;
+        async move {
+            while updates.changed().await.is_ok() {
+                let interval = heartbeat_interval(&updates.borrow_and_update());
+                pulse.set_interval(interval).await;
+            }
+        }
+    });
+
+    // Feed each line of stdin to the agent, stopping at end of input
+    let handle = agent.handle();
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if handle.send(Message::user(line.to_string())).await.is_err() {
+                return;
+            }
+        }
+        let _ = handle.stop().await;
+    });
+
+    // Start the main agent loop
+    info!("🚀 Starting agent loop...");
+    let result = agent.run().await;
+    pulse.stop().await;
+    monitor.abort();
+    pulse_updates.abort();
+    result?;
+    info!("Agent shutdown gracefully");
+    Ok(())
+}
+
+fn heartbeat_interval(config: &Config) -> Duration {
+    Duration::from_secs(config.agent.heartbeat_interval_secs)
+}
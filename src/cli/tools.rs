@@ -0,0 +1,27 @@
+//! `skynet tools` subcommands
+
+use clap::Subcommand;
+use skynet_rust::{Config, Result, SkynetAgent};
+
+#[derive(Debug, Subcommand)]
+pub enum ToolsCommand {
+    /// List the tools the agent may call, with their input schemas
+    List,
+}
+
+pub async fn execute(command: ToolsCommand, config: Config) -> Result<()> {
+    match command {
+        ToolsCommand::List => {
+            let agent = SkynetAgent::new(config).await?;
+            let tools = agent.tools();
+            if tools.is_empty() {
+                println!("No tools registered");
+            }
+            for tool in tools {
+                println!("{}: {}", tool.name, tool.description);
+                println!("  input: {}", tool.input_schema);
+            }
+        }
+    }
+    Ok(())
+}
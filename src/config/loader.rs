@@ -0,0 +1,846 @@
+use crate::config::{Config, ConfigIssue};
+use crate::{Result, SkynetError};
+use config::{ConfigError, File, FileFormat, Map, Source, Value, ValueKind};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// System-wide configuration file
+pub const SYSTEM_CONFIG_PATH: &str = "/etc/skynet/skynet.toml";
+
+/// Configuration file looked up in the working directory
+pub const PROJECT_CONFIG_PATH: &str = "skynet.toml";
+
+/// Prefix of environment variables read into the configuration
+pub const ENV_PREFIX: &str = "SKYNET_";
+
+/// Separates nested keys in generic environment variables, as in
+/// `SKYNET_AGENT__TEMPERATURE`
+pub const ENV_SEPARATOR: &str = "__";
+
+/// Environment variables with names of their own, and the keys they set.
+/// Earlier entries lose to later ones that set the same key.
+const ENV_ALIASES: &[(&str, &str)] = &[
+    ("CLAUDE_API_KEY", "anthropic_api_key"),
+    ("ANTHROPIC_API_KEY", "anthropic_api_key"),
+    ("SKYNET_MODEL", "default_model"),
+    ("SKYNET_PROVIDER", "provider"),
+    ("SKYNET_DB_BACKEND", "database.backend"),
+    ("SKYNET_DB_PATH", "database.path"),
+    ("SKYNET_DB_MAX_CONNECTIONS", "database.max_connections"),
+    ("SKYNET_MAX_CONTEXT", "agent.max_context_messages"),
+    ("SKYNET_HEARTBEAT_INTERVAL", "agent.heartbeat_interval_secs"),
+    ("SKYNET_MAX_TOKENS", "agent.max_tokens"),
+    ("SKYNET_TEMPERATURE", "agent.temperature"),
+    ("SKYNET_MAX_TOOL_ITERATIONS", "agent.max_tool_iterations"),
+    ("SKYNET_MAX_RETRIES", "agent.retry.max_retries"),
+    ("SKYNET_CONTEXT_WINDOW", "agent.context_window_tokens"),
+    (
+        "SKYNET_CONTEXT_SEARCH_RESULTS",
+        "agent.context_search_results",
+    ),
+    ("SKYNET_SPEND_LIMIT_USD", "agent.spend_limit_usd"),
+    (
+        "SKYNET_COMPACTION_THRESHOLD",
+        "agent.compaction.threshold_messages",
+    ),
+    ("SKYNET_LOG_LEVEL", "logging.level"),
+    ("SKYNET_FILE_LOGGING", "logging.file_logging"),
+    ("SKYNET_LOG_FILE", "logging.log_file"),
+    ("SKYNET_LOG_FORMAT", "logging.format"),
+];
+
+/// A source of configuration values, in increasing order of precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConfigLayer {
+    /// Built-in defaults
+    Defaults,
+    /// `/etc/skynet/skynet.toml`
+    SystemFile,
+    /// `~/.config/skynet/skynet.toml`
+    UserFile,
+    /// `./skynet.toml`, or the file given with `--config`
+    ProjectFile,
+    /// `SKYNET_*` and API key environment variables
+    Environment,
+    /// Overrides given on the command line
+    CommandLine,
+}
+
+impl fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Defaults => "defaults",
+            Self::SystemFile => "system file",
+            Self::UserFile => "user file",
+            Self::ProjectFile => "project file",
+            Self::Environment => "environment",
+            Self::CommandLine => "command line",
+        })
+    }
+}
+
+/// Where an effective configuration value was set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueOrigin {
+    /// Layer that set the value last
+    pub layer: ConfigLayer,
+    /// File path, environment variable or flag within the layer
+    pub location: Option<String>,
+}
+
+impl ValueOrigin {
+    fn defaults() -> Self {
+        Self {
+            layer: ConfigLayer::Defaults,
+            location: None,
+        }
+    }
+}
+
+impl fmt::Display for ValueOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{} ({})", self.layer, location),
+            None => write!(f, "{}", self.layer),
+        }
+    }
+}
+
+/// A configuration file the loader looked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigFile {
+    pub layer: ConfigLayer,
+    pub path: PathBuf,
+    /// Whether the file existed and was read
+    pub loaded: bool,
+}
+
+/// Which layer each effective configuration value came from
+#[derive(Debug, Clone, Default)]
+pub struct ConfigReport {
+    files: Vec<ConfigFile>,
+    origins: BTreeMap<String, ValueOrigin>,
+    issues: Vec<ConfigIssue>,
+}
+
+impl ConfigReport {
+    /// Files the loader looked for, lowest precedence first
+    pub fn files(&self) -> &[ConfigFile] {
+        &self.files
+    }
+
+    /// Origin of the value at a dotted key such as `agent.temperature`
+    pub fn origin(&self, key: &str) -> ValueOrigin {
+        self.origins
+            .get(key)
+            .cloned()
+            .unwrap_or_else(ValueOrigin::defaults)
+    }
+
+    /// Values that do not fit their field and were left at their defaults,
+    /// and secrets that failed to resolve; validation reports them along
+    /// with its own issues
+    pub fn issues(&self) -> &[ConfigIssue] {
+        &self.issues
+    }
+
+    /// Every effective value of `config` as a dotted key, its value and its
+    /// origin, sorted by key
+    pub fn entries(
+        &self,
+        config: &Config,
+    ) -> Result<Vec<(String, serde_json::Value, ValueOrigin)>> {
+        Ok(flatten(config)?
+            .into_iter()
+            .map(|(key, value)| {
+                let origin = self.origin(&key);
+                (key, value, origin)
+            })
+            .collect())
+    }
+
+    fn record(&mut self, keys: Vec<String>, origin: ValueOrigin) {
+        for key in keys {
+            self.origins.insert(key, origin.clone());
+        }
+    }
+
+    /// Drop the origins of the value or table at `key`, which is back at its
+    /// default
+    fn forget(&mut self, key: &str) {
+        let prefix = format!("{}.", key);
+        self.origins
+            .retain(|child, _| child != key && !child.starts_with(&prefix));
+    }
+
+    /// Origin of the value at `key`, or of the first value set under it
+    /// when `key` names a table
+    fn origin_within(&self, key: &str) -> ValueOrigin {
+        let prefix = format!("{}.", key);
+        self.origins
+            .get(key)
+            .or_else(|| {
+                self.origins
+                    .iter()
+                    .find(|(child, _)| child.starts_with(&prefix))
+                    .map(|(_, origin)| origin)
+            })
+            .cloned()
+            .unwrap_or_else(ValueOrigin::defaults)
+    }
+}
+
+/// Builds a [`Config`] from layered sources.
+///
+/// Later layers override earlier ones key by key: built-in defaults, the
+/// system file, the user file, the project file, environment variables and
+/// finally command-line overrides. Files that do not exist are skipped unless
+/// required; files that exist but do not parse are an error.
+///
+/// Besides the named variables in the README, any key can be set from the
+/// environment as `SKYNET_<SECTION>__<KEY>`, e.g. `SKYNET_AGENT__TEMPERATURE`.
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    system_file: Option<PathBuf>,
+    user_file: Option<PathBuf>,
+    project_file: Option<PathBuf>,
+    project_required: bool,
+    env: Vec<(String, String)>,
+    overrides: Vec<(String, String)>,
+}
+
+impl Default for ConfigLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigLoader {
+    /// Loader for the standard file locations and the process environment
+    pub fn new() -> Self {
+        Self {
+            system_file: Some(PathBuf::from(SYSTEM_CONFIG_PATH)),
+            user_file: user_config_path(),
+            project_file: Some(PathBuf::from(PROJECT_CONFIG_PATH)),
+            project_required: false,
+            env: std::env::vars().collect(),
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Loader with no files and an empty environment
+    pub fn empty() -> Self {
+        Self {
+            system_file: None,
+            user_file: None,
+            project_file: None,
+            project_required: false,
+            env: Vec::new(),
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Read the system layer from `path` instead, or skip it
+    pub fn system_file(mut self, path: Option<impl Into<PathBuf>>) -> Self {
+        self.system_file = path.map(Into::into);
+        self
+    }
+
+    /// Read the user layer from `path` instead, or skip it
+    pub fn user_file(mut self, path: Option<impl Into<PathBuf>>) -> Self {
+        self.user_file = path.map(Into::into);
+        self
+    }
+
+    /// Read the project layer from `path`, which must exist
+    pub fn project_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.project_file = Some(path.into());
+        self.project_required = true;
+        self
+    }
+
+    /// Use these variables as the environment instead of the process's
+    pub fn env<K, V>(mut self, vars: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.env = vars
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        self
+    }
+
+    /// Override a dotted key such as `agent.temperature` in the
+    /// command-line layer
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    /// Files this loader reads, lowest precedence first
+    pub fn files(&self) -> Vec<PathBuf> {
+        [&self.system_file, &self.user_file, &self.project_file]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Load the configuration, resolve its secrets and validate it.
+    /// Resolving secrets may block: it reads files and runs commands.
+    pub fn load(&self) -> Result<Config> {
+        let (mut config, mut report) = self.load_with_report()?;
+        for mut issue in config.resolve_secrets() {
+            issue.origin = Some(report.origin(&issue.key));
+            report.issues.push(issue);
+        }
+        config.validate_with(&report)?;
+        Ok(config)
+    }
+
+    /// Load the configuration, without validating it, along with where each
+    /// value came from. Values that do not fit their field are left at their
+    /// defaults and listed in [`ConfigReport::issues`].
+    pub fn load_with_report(&self) -> Result<(Config, ConfigReport)> {
+        let mut builder = config::Config::builder();
+        let mut report = ConfigReport::default();
+
+        let files = [
+            (ConfigLayer::SystemFile, &self.system_file, false),
+            (ConfigLayer::UserFile, &self.user_file, false),
+            (
+                ConfigLayer::ProjectFile,
+                &self.project_file,
+                self.project_required,
+            ),
+        ];
+        for (layer, path, required) in files {
+            let Some(path) = path else { continue };
+            let loaded = path.is_file();
+            if !loaded && required {
+                return Err(SkynetError::config(format!(
+                    "Configuration file {} does not exist",
+                    path.display()
+                )));
+            }
+            report.files.push(ConfigFile {
+                layer,
+                path: path.clone(),
+                loaded,
+            });
+            if !loaded {
+                continue;
+            }
+
+            let source = File::new(&path.to_string_lossy(), FileFormat::Toml);
+            let values = source.collect().map_err(|e| {
+                SkynetError::config(format!("Failed to parse {}", path.display())).with_source(e)
+            })?;
+            let mut keys = Vec::new();
+            for (key, value) in &values {
+                flatten_value(key.clone(), value, &mut keys);
+            }
+            report.record(
+                keys,
+                ValueOrigin {
+                    layer,
+                    location: Some(path.display().to_string()),
+                },
+            );
+            builder = builder.add_source(source);
+        }
+
+        let env = env_assignments(&self.env);
+        let overrides = self
+            .overrides
+            .iter()
+            .map(|(key, value)| Assignment {
+                key: key.clone(),
+                value: value.clone(),
+                location: format!("--set {}={}", key, value),
+                origin: format!("command-line flag --set {}", key),
+            })
+            .collect();
+        for (layer, assignments) in [
+            (ConfigLayer::Environment, env),
+            (ConfigLayer::CommandLine, overrides),
+        ] {
+            for assignment in &assignments {
+                report.origins.insert(
+                    assignment.key.clone(),
+                    ValueOrigin {
+                        layer,
+                        location: Some(assignment.location.clone()),
+                    },
+                );
+            }
+            builder = builder.add_source(Assignments(assignments));
+        }
+
+        let values = builder
+            .build()
+            .and_then(|config| config.collect())
+            .map_err(|e| SkynetError::config("Failed to load configuration").with_source(e))?;
+        let config = deserialize(values, &mut report)?;
+        Ok((config, report))
+    }
+}
+
+/// Deserialize `values`, dropping each value that fails to deserialize and
+/// recording it in `report`, so every bad value is reported rather than the
+/// first
+fn deserialize(mut values: Map<String, Value>, report: &mut ConfigReport) -> Result<Config> {
+    loop {
+        let error = match try_deserialize(&values) {
+            Ok(config) => {
+                report.issues.sort_by(|a, b| a.key.cmp(&b.key));
+                return Ok(config);
+            }
+            Err(error) => error,
+        };
+        let Some(path) = offending_path(&values, &error) else {
+            return Err(SkynetError::config("Failed to load configuration").with_source(error));
+        };
+        remove(&mut values, &path);
+
+        let message = match &error {
+            ConfigError::Type {
+                unexpected,
+                expected,
+                ..
+            } => format!("invalid type: {}, expected {}", unexpected, expected),
+            error => error.to_string(),
+        };
+        let key = path.join(".");
+        let origin = report.origin_within(&key);
+        report.forget(&key);
+        report.issues.push(ConfigIssue {
+            key,
+            message,
+            origin: Some(origin),
+        });
+    }
+}
+
+fn try_deserialize(values: &Map<String, Value>) -> std::result::Result<Config, ConfigError> {
+    Value::new(None, ValueKind::Table(values.clone())).try_deserialize()
+}
+
+/// Path of the value or table in `values` that causes `error`.
+///
+/// Type errors name their key. Other errors, such as unknown enum variants
+/// or missing fields, do not, so the value is found by removing values one
+/// at a time, deepest first, until the error goes away.
+fn offending_path(values: &Map<String, Value>, error: &ConfigError) -> Option<Vec<String>> {
+    if let ConfigError::Type { key: Some(key), .. } = error {
+        let path: Vec<String> = key.split('.').map(str::to_string).collect();
+        if lookup(values, &path).is_some() {
+            return Some(path);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (key, value) in values {
+        table_paths(vec![key.clone()], value, &mut candidates);
+    }
+    candidates.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+
+    let message = error.to_string();
+    candidates.into_iter().find(|path| {
+        let mut rest = values.clone();
+        remove(&mut rest, path);
+        match try_deserialize(&rest) {
+            Ok(_) => true,
+            // Removing a required field only trades one error for another
+            Err(other) => {
+                let other = other.to_string();
+                let missing = format!("missing field `{}`", path[path.len() - 1]);
+                other != message && other != missing
+            }
+        }
+    })
+}
+
+/// Paths of every value and table under `value`, itself included
+fn table_paths(path: Vec<String>, value: &Value, paths: &mut Vec<Vec<String>>) {
+    if let ValueKind::Table(table) = &value.kind {
+        for (child, value) in table {
+            let mut child_path = path.clone();
+            child_path.push(child.clone());
+            table_paths(child_path, value, paths);
+        }
+    }
+    paths.push(path);
+}
+
+fn lookup<'a>(values: &'a Map<String, Value>, path: &[String]) -> Option<&'a Value> {
+    let (first, rest) = path.split_first()?;
+    let value = values.get(first)?;
+    match (&value.kind, rest.is_empty()) {
+        (_, true) => Some(value),
+        (ValueKind::Table(table), false) => lookup(table, rest),
+        _ => None,
+    }
+}
+
+fn remove(values: &mut Map<String, Value>, path: &[String]) {
+    match path {
+        [] => {}
+        [last] => {
+            values.remove(last);
+        }
+        [first, rest @ ..] => {
+            if let Some(Value {
+                kind: ValueKind::Table(table),
+                ..
+            }) = values.get_mut(first)
+            {
+                remove(table, rest);
+            }
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/skynet/skynet.toml`, or `~/.config/skynet/skynet.toml`
+pub fn user_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("skynet").join(PROJECT_CONFIG_PATH))
+}
+
+/// One key set from the environment or the command line
+#[derive(Debug, Clone)]
+struct Assignment {
+    key: String,
+    value: String,
+    /// Variable name or flag, as shown in the report
+    location: String,
+    /// Describes the location in parse errors
+    origin: String,
+}
+
+/// Dotted-key assignments as a `config` source; values are strings that are
+/// parsed into the field's type on deserialization
+#[derive(Debug, Clone)]
+struct Assignments(Vec<Assignment>);
+
+impl Source for Assignments {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> std::result::Result<Map<String, Value>, config::ConfigError> {
+        Ok(self
+            .0
+            .iter()
+            .map(|assignment| {
+                let value = Value::new(
+                    Some(&assignment.origin),
+                    ValueKind::String(assignment.value.clone()),
+                );
+                (assignment.key.clone(), value)
+            })
+            .collect())
+    }
+}
+
+/// Keys set by `vars`: named aliases first, then generic
+/// `SKYNET_<SECTION>__<KEY>` variables
+fn env_assignments(vars: &[(String, String)]) -> Vec<Assignment> {
+    let lookup: BTreeMap<&str, &str> = vars
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+
+    let mut assignments: Vec<Assignment> = ENV_ALIASES
+        .iter()
+        .filter_map(|(name, key)| {
+            lookup.get(name).map(|value| Assignment {
+                key: key.to_string(),
+                value: value.to_string(),
+                location: name.to_string(),
+                origin: format!("environment variable {}", name),
+            })
+        })
+        .collect();
+
+    for (name, value) in lookup {
+        if ENV_ALIASES.iter().any(|(alias, _)| *alias == name) {
+            continue;
+        }
+        let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if rest.is_empty() {
+            continue;
+        }
+        assignments.push(Assignment {
+            key: rest
+                .to_lowercase()
+                .split(ENV_SEPARATOR)
+                .collect::<Vec<_>>()
+                .join("."),
+            value: value.to_string(),
+            location: name.to_string(),
+            origin: format!("environment variable {}", name),
+        });
+    }
+    assignments
+}
+
+/// Dotted keys of the leaves under `value`
+fn flatten_value(key: String, value: &Value, keys: &mut Vec<String>) {
+    match &value.kind {
+        ValueKind::Table(table) => {
+            for (child, value) in table {
+                flatten_value(format!("{}.{}", key, child), value, keys);
+            }
+        }
+        _ => keys.push(key),
+    }
+}
+
+/// Every value of `config` as a dotted key and its value, sorted by key
+pub(crate) fn flatten(config: &Config) -> Result<Vec<(String, serde_json::Value)>> {
+    let value = serde_json::to_value(config)
+        .map_err(|e| SkynetError::config("Failed to serialize configuration").with_source(e))?;
+    let mut leaves = Vec::new();
+    flatten_json(String::new(), value, &mut leaves);
+    leaves.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(leaves)
+}
+
+fn flatten_json(
+    key: String,
+    value: serde_json::Value,
+    leaves: &mut Vec<(String, serde_json::Value)>,
+) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (child, value) in object {
+                let child = if key.is_empty() {
+                    child
+                } else {
+                    format!("{}.{}", key, child)
+                };
+                flatten_json(child, value, leaves);
+            }
+        }
+        value => leaves.push((key, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MemoryBackend;
+
+    /// A TOML file under the system temp directory, removed on drop
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "skynet-loader-{}-{}.toml",
+                std::process::id(),
+                name
+            ));
+            std::fs::write(&path, contents).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn origin(layer: ConfigLayer, location: &str) -> ValueOrigin {
+        ValueOrigin {
+            layer,
+            location: Some(location.to_string()),
+        }
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let system = TempFile::new(
+            "precedence-system",
+            r#"
+default_model = "system-model"
+
+[agent]
+temperature = 0.1
+max_tokens = 100
+max_tool_iterations = 3
+"#,
+        );
+        let project = TempFile::new(
+            "precedence-project",
+            r#"
+[agent]
+temperature = 0.2
+max_tokens = 200
+max_tool_iterations = 4
+"#,
+        );
+        let (config, report) = ConfigLoader::empty()
+            .system_file(Some(&system.0))
+            .project_file(&project.0)
+            .env([
+                ("SKYNET_AGENT__TEMPERATURE", "0.3"),
+                ("SKYNET_MAX_TOKENS", "300"),
+            ])
+            .set("agent.temperature", "0.4")
+            .load_with_report()
+            .unwrap();
+
+        assert_eq!(config.agent.temperature, 0.4);
+        assert_eq!(config.agent.max_tokens, 300);
+        assert_eq!(config.agent.max_tool_iterations, 4);
+        assert_eq!(config.default_model, "system-model");
+        assert_eq!(
+            config.agent.max_context_messages,
+            Config::default().agent.max_context_messages
+        );
+
+        let system_path = system.0.display().to_string();
+        let project_path = project.0.display().to_string();
+        assert_eq!(
+            report.origin("agent.temperature"),
+            origin(ConfigLayer::CommandLine, "--set agent.temperature=0.4")
+        );
+        assert_eq!(
+            report.origin("agent.max_tokens"),
+            origin(ConfigLayer::Environment, "SKYNET_MAX_TOKENS")
+        );
+        assert_eq!(
+            report.origin("agent.max_tool_iterations"),
+            origin(ConfigLayer::ProjectFile, &project_path)
+        );
+        assert_eq!(
+            report.origin("default_model"),
+            origin(ConfigLayer::SystemFile, &system_path)
+        );
+        assert_eq!(
+            report.origin("agent.max_context_messages"),
+            ValueOrigin::defaults()
+        );
+
+        let files: Vec<_> = report.files().iter().map(|file| file.layer).collect();
+        assert_eq!(files, [ConfigLayer::SystemFile, ConfigLayer::ProjectFile]);
+    }
+
+    #[test]
+    fn missing_optional_files_are_skipped() {
+        let missing = std::env::temp_dir().join("skynet-loader-does-not-exist.toml");
+        let (_, report) = ConfigLoader::empty()
+            .user_file(Some(&missing))
+            .load_with_report()
+            .unwrap();
+        assert!(!report.files()[0].loaded);
+
+        assert!(ConfigLoader::empty()
+            .project_file(&missing)
+            .load_with_report()
+            .is_err());
+    }
+
+    #[test]
+    fn maps_generic_environment_variables_to_nested_keys() {
+        let (config, report) = ConfigLoader::empty()
+            .env([
+                ("SKYNET_AGENT__COMPACTION__KEEP_RECENT", "7"),
+                ("SKYNET_Database__Backend", "memory"),
+                ("SKYNET_LOGGING__LEVEL", "debug"),
+                ("SKYNET_", "ignored"),
+                ("OTHER_AGENT__MAX_TOKENS", "1"),
+            ])
+            .load_with_report()
+            .unwrap();
+
+        assert_eq!(config.agent.compaction.keep_recent, 7);
+        assert_eq!(config.database.backend, MemoryBackend::Memory);
+        assert_eq!(config.logging.level, "debug");
+        assert_eq!(config.agent.max_tokens, Config::default().agent.max_tokens);
+        assert_eq!(
+            report.origin("agent.compaction.keep_recent"),
+            origin(
+                ConfigLayer::Environment,
+                "SKYNET_AGENT__COMPACTION__KEEP_RECENT"
+            )
+        );
+        assert_eq!(
+            report.origin("database.backend"),
+            origin(ConfigLayer::Environment, "SKYNET_Database__Backend")
+        );
+    }
+
+    #[test]
+    fn generic_variables_override_aliases() {
+        let (config, report) = ConfigLoader::empty()
+            .env([
+                ("SKYNET_MAX_TOKENS", "300"),
+                ("SKYNET_AGENT__MAX_TOKENS", "400"),
+                ("CLAUDE_API_KEY", "sk-claude"),
+                ("ANTHROPIC_API_KEY", "sk-anthropic"),
+            ])
+            .load_with_report()
+            .unwrap();
+
+        assert_eq!(config.agent.max_tokens, 400);
+        assert_eq!(
+            report.origin("agent.max_tokens"),
+            origin(ConfigLayer::Environment, "SKYNET_AGENT__MAX_TOKENS")
+        );
+        assert_eq!(config.anthropic_api_key.expose(), "sk-anthropic");
+        assert_eq!(
+            report.origin("anthropic_api_key"),
+            origin(ConfigLayer::Environment, "ANTHROPIC_API_KEY")
+        );
+    }
+
+    #[test]
+    fn keeps_the_case_of_file_and_command_line_keys() {
+        let project = TempFile::new(
+            "case",
+            r#"
+provider = "MyClaude"
+
+[providers.MyClaude]
+type = "anthropic"
+model = "claude-3-haiku-20240307"
+
+[pricing."GPT-4o"]
+input_per_mtok = 2.5
+output_per_mtok = 10.0
+"#,
+        );
+        let (config, report) = ConfigLoader::empty()
+            .project_file(&project.0)
+            .set("providers.MyClaude.model", "claude-3-opus-20240229")
+            .load_with_report()
+            .unwrap();
+
+        assert_eq!(config.provider.as_deref(), Some("MyClaude"));
+        assert_eq!(config.providers["MyClaude"].model, "claude-3-opus-20240229");
+        assert!(config.pricing.contains_key("GPT-4o"));
+        assert_eq!(
+            report.origin("providers.MyClaude.model"),
+            origin(
+                ConfigLayer::CommandLine,
+                "--set providers.MyClaude.model=claude-3-opus-20240229"
+            )
+        );
+        assert_eq!(
+            report.origin("pricing.GPT-4o.input_per_mtok"),
+            origin(ConfigLayer::ProjectFile, &project.0.display().to_string())
+        );
+    }
+}
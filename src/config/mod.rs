@@ -0,0 +1,496 @@
+pub mod loader;
+pub mod secret;
+pub mod validate;
+pub mod watch;
+
+pub use loader::{ConfigFile, ConfigLayer, ConfigLoader, ConfigReport, ValueOrigin};
+pub use secret::Secret;
+pub use validate::ConfigIssue;
+pub use watch::{ConfigChanges, ConfigWatcher};
+
+use crate::core::compaction::CompactionConfig;
+use crate::core::usage::ModelPricing;
+use crate::providers::fallback::FallbackConfig;
+use crate::providers::r#trait::ProviderConfig;
+use crate::providers::retry::RetryConfig;
+use crate::{Result, SkynetError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Main configuration for SKYNET.
+///
+/// Every section and field has a default, so a file only needs the values
+/// it changes; see [`ConfigLoader`] for how files and the environment are
+/// layered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Anthropic API key for Claude, literal or an `env:`, `file:` or `cmd:`
+    /// reference
+    pub anthropic_api_key: Secret,
+
+    /// Default model to use
+    pub default_model: String,
+
+    /// Name of the entry in `providers` to use. When unset, the built-in
+    /// Anthropic provider is configured from `anthropic_api_key`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+
+    /// Database configuration
+    pub database: DatabaseConfig,
+
+    /// Agent configuration
+    pub agent: AgentConfig,
+
+    /// Logging configuration
+    pub logging: LoggingConfig,
+
+    /// Which registered tools the agent may call
+    pub tools: ToolsConfig,
+
+    /// Named provider definitions (`[providers.<name>]` tables)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub providers: BTreeMap<String, ProviderConfig>,
+
+    /// Providers to fail over to when the active one is unavailable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<FallbackConfig>,
+
+    /// Per-model prices used for cost accounting (`[pricing."<model>"]`)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub pricing: BTreeMap<String, ModelPricing>,
+}
+
+/// Database configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    /// Memory backend to use
+    pub backend: MemoryBackend,
+
+    /// SQLite database path
+    pub path: String,
+
+    /// Maximum number of connections
+    pub max_connections: u32,
+}
+
+/// Storage backend for agent memory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryBackend {
+    /// Persistent SQLite database at `DatabaseConfig::path`
+    #[default]
+    Sqlite,
+
+    /// Volatile in-process storage, lost on restart
+    Memory,
+}
+
+impl std::str::FromStr for MemoryBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sqlite" => Ok(Self::Sqlite),
+            "memory" => Ok(Self::Memory),
+            other => Err(format!("unknown memory backend: {}", other)),
+        }
+    }
+}
+
+/// Agent configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentConfig {
+    /// Maximum number of messages to keep in context
+    pub max_context_messages: usize,
+
+    /// Heartbeat interval in seconds
+    pub heartbeat_interval_secs: u64,
+
+    /// Maximum tokens per request
+    pub max_tokens: u32,
+
+    /// Temperature for LLM generation
+    #[serde(serialize_with = "short_float::serialize")]
+    pub temperature: f32,
+
+    /// Maximum number of tool-use round trips per input message
+    pub max_tool_iterations: usize,
+
+    /// Retry policy for provider calls, unless a provider sets its own
+    pub retry: RetryConfig,
+
+    /// Context window of the model in tokens, unless a provider sets its own
+    pub context_window_tokens: usize,
+
+    /// Relevant older messages recalled from memory into each request
+    pub context_search_results: usize,
+
+    /// Stop calling providers once this much has been spent, in USD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spend_limit_usd: Option<f64>,
+
+    /// Summarization of old history
+    pub compaction: CompactionConfig,
+}
+
+/// Logging configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Log level (trace, debug, info, warn, error)
+    pub level: String,
+
+    /// Whether to log to file
+    pub file_logging: bool,
+
+    /// Log file path (if file_logging is true)
+    pub log_file: Option<String>,
+
+    /// Format of log lines, on stderr and in the log file
+    pub format: LogFormat,
+
+    /// How often to start a new log file; rotated files get a date suffix
+    pub rotation: LogRotation,
+
+    /// Start a new log file once the current one would grow past this size;
+    /// rolled-over files get a numeric suffix, `.1` being the newest. Cannot
+    /// be combined with time `rotation`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_size_bytes: Option<u64>,
+
+    /// Log files to keep, including the current one, deleting older ones;
+    /// all when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_log_files: Option<usize>,
+}
+
+/// Tool permissions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolsConfig {
+    /// Tools the agent may call; every registered tool when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed: Option<Vec<String>>,
+
+    /// Tools the agent may not call, even when listed in `allowed`
+    pub denied: Vec<String>,
+}
+
+impl ToolsConfig {
+    /// Whether the tool named `name` may be called
+    pub fn permits(&self, name: &str) -> bool {
+        let allowed = match &self.allowed {
+            Some(allowed) => allowed.iter().any(|tool| tool == name),
+            None => true,
+        };
+        allowed && !self.denied.iter().any(|tool| tool == name)
+    }
+}
+
+/// Format of log output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+
+    /// One JSON object per event, with the fields of its spans, such as the
+    /// session and tool
+    Json,
+}
+
+/// When to rotate the log file by time; see `max_size_bytes` for rotation
+/// by size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    /// Keep writing to `log_file`
+    #[default]
+    Never,
+    Minutely,
+    Hourly,
+    Daily,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            anthropic_api_key: Secret::default(),
+            default_model: "claude-3-sonnet-20240229".to_string(),
+            provider: None,
+            database: DatabaseConfig::default(),
+            agent: AgentConfig::default(),
+            logging: LoggingConfig::default(),
+            tools: ToolsConfig::default(),
+            providers: BTreeMap::new(),
+            fallback: None,
+            pricing: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            backend: MemoryBackend::Sqlite,
+            path: "./skynet.db".to_string(),
+            max_connections: 10,
+        }
+    }
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            max_context_messages: 50,
+            heartbeat_interval_secs: 30,
+            max_tokens: 1000,
+            temperature: 0.7,
+            max_tool_iterations: 10,
+            retry: RetryConfig::default(),
+            context_window_tokens: 200_000,
+            context_search_results: 5,
+            spend_limit_usd: None,
+            compaction: CompactionConfig::default(),
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            file_logging: false,
+            log_file: None,
+            format: LogFormat::Text,
+            rotation: LogRotation::Never,
+            max_size_bytes: None,
+            max_log_files: None,
+        }
+    }
+}
+
+impl Config {
+    /// Resolve the configuration of the provider the agent should use.
+    ///
+    /// `max_tokens`, `temperature`, `context_window` and `retry` not set on
+    /// the provider fall back to the `[agent]` section.
+    pub fn active_provider(&self) -> Result<ProviderConfig> {
+        self.resolve_provider(self.provider.as_deref())
+    }
+
+    /// Name the active provider is reported under
+    pub fn active_provider_name(&self) -> &str {
+        self.provider.as_deref().unwrap_or("anthropic")
+    }
+
+    /// Resolve the provider named `name` in `[providers]`, or the implicit
+    /// Anthropic provider for `None`, applying the `[agent]` fallbacks
+    pub fn resolve_provider(&self, name: Option<&str>) -> Result<ProviderConfig> {
+        let mut provider = match name {
+            Some(name) => self.providers.get(name).cloned().ok_or_else(|| {
+                SkynetError::config(format!("Provider '{}' is not defined in [providers]", name))
+            })?,
+            None if self.anthropic_api_key.is_empty() => {
+                return Err(SkynetError::config(
+                    "No Anthropic API key: set anthropic_api_key or ANTHROPIC_API_KEY",
+                ))
+            }
+            None => ProviderConfig {
+                provider_type: "anthropic".to_string(),
+                api_key: self.anthropic_api_key.clone(),
+                model: self.default_model.clone(),
+                base_url: None,
+                max_tokens: None,
+                temperature: None,
+                context_window: None,
+                retry: None,
+            },
+        };
+
+        provider.max_tokens = provider.max_tokens.or(Some(self.agent.max_tokens));
+        provider.temperature = provider.temperature.or(Some(self.agent.temperature));
+        provider.context_window = provider
+            .context_window
+            .or(Some(self.agent.context_window_tokens));
+        provider.retry = provider.retry.or_else(|| Some(self.agent.retry.clone()));
+        Ok(provider)
+    }
+
+    /// Resolve every `env:`, `file:` and `cmd:` API key reference in place,
+    /// returning an issue for each that fails to resolve. Resolving may
+    /// block: it reads files and runs commands.
+    pub fn resolve_secrets(&mut self) -> Vec<ConfigIssue> {
+        let providers = self
+            .providers
+            .iter_mut()
+            .map(|(name, provider)| (format!("providers.{}.api_key", name), &mut provider.api_key));
+        std::iter::once(("anthropic_api_key".to_string(), &mut self.anthropic_api_key))
+            .chain(providers)
+            .filter_map(|(key, secret)| match secret.resolved() {
+                Ok(resolved) => {
+                    *secret = resolved;
+                    None
+                }
+                Err(e) => {
+                    let message = match e {
+                        SkynetError::Config {
+                            message,
+                            source: Some(source),
+                        } => format!("{}: {}", message, source),
+                        SkynetError::Config { message, .. } => message,
+                        e => e.to_string(),
+                    };
+                    Some(ConfigIssue {
+                        key,
+                        message,
+                        origin: None,
+                    })
+                }
+            })
+            .collect()
+    }
+
+    /// Problems with this configuration: out-of-range values, missing
+    /// directories, unknown models and placeholder API keys
+    pub fn issues(&self) -> Vec<ConfigIssue> {
+        validate::issues(self, None)
+    }
+
+    /// Fail with a single error listing every problem with this configuration
+    pub fn validate(&self) -> Result<()> {
+        Self::check(validate::issues(self, None))
+    }
+
+    /// Like [`validate`](Self::validate), naming the layer each offending
+    /// value came from
+    pub fn validate_with(&self, report: &ConfigReport) -> Result<()> {
+        Self::check(validate::issues(self, Some(report)))
+    }
+
+    fn check(issues: Vec<ConfigIssue>) -> Result<()> {
+        if issues.is_empty() {
+            return Ok(());
+        }
+        let count = match issues.len() {
+            1 => "1 problem".to_string(),
+            n => format!("{} problems", n),
+        };
+        let list: Vec<String> = issues
+            .iter()
+            .map(|issue| format!("  - {}", issue))
+            .collect();
+        Err(SkynetError::config(format!(
+            "{} in the configuration:\n{}",
+            count,
+            list.join("\n")
+        )))
+    }
+
+    /// Load configuration from the standard files, the environment and
+    /// defaults, layered as described on [`ConfigLoader`]
+    pub async fn load() -> Result<Self> {
+        // Loading reads files and may run `cmd:` secret commands
+        tokio::task::spawn_blocking(|| ConfigLoader::new().load())
+            .await
+            .map_err(|e| SkynetError::config("Configuration loading task failed").with_source(e))?
+    }
+
+    /// Load configuration from a TOML file, with defaults for missing values.
+    /// The result is not validated.
+    pub async fn load_from_file(path: &str) -> Result<Self> {
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| SkynetError::config(format!("Failed to read {}", path)).with_source(e))?;
+        let config: Config = toml::from_str(&content)
+            .map_err(|e| SkynetError::config(format!("Failed to parse {}", path)).with_source(e))?;
+        Ok(config)
+    }
+
+    /// Load configuration from environment variables and defaults only
+    pub fn load_from_env() -> Result<Self> {
+        ConfigLoader::empty().env(std::env::vars()).load()
+    }
+
+    /// Save configuration to a TOML file, with secrets written as their
+    /// references. Fails if any secret was given literally, since it would
+    /// be saved redacted.
+    pub async fn save_to_file(&self, path: &str) -> Result<()> {
+        let providers = self
+            .providers
+            .iter()
+            .map(|(name, provider)| (format!("providers.{}.api_key", name), &provider.api_key));
+        let literal: Vec<String> =
+            std::iter::once(("anthropic_api_key".to_string(), &self.anthropic_api_key))
+                .chain(providers)
+                .filter(|(_, secret)| secret.is_literal())
+                .map(|(key, _)| key)
+                .collect();
+        if !literal.is_empty() {
+            return Err(SkynetError::config(format!(
+                "Not saving {}: {} given literally; use an env:, file: or cmd: reference",
+                path,
+                literal.join(", ")
+            )));
+        }
+
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| SkynetError::config("Failed to serialize configuration").with_source(e))?;
+        tokio::fs::write(path, content)
+            .await
+            .map_err(|e| SkynetError::config(format!("Failed to write {}", path)).with_source(e))?;
+        Ok(())
+    }
+
+    /// Create a default configuration file
+    pub async fn create_default_config(path: &str) -> Result<()> {
+        let default_config = Config {
+            anthropic_api_key: Secret::unresolved("env:ANTHROPIC_API_KEY"),
+            pricing: BTreeMap::from([(
+                "claude-3-sonnet".to_string(),
+                ModelPricing {
+                    input_per_mtok: 3.0,
+                    output_per_mtok: 15.0,
+                    cache_write_per_mtok: 3.75,
+                    cache_read_per_mtok: 0.3,
+                },
+            )]),
+            ..Config::default()
+        };
+
+        default_config.save_to_file(path).await?;
+        Ok(())
+    }
+}
+
+/// Serializers for `f32` settings.
+///
+/// TOML stores floats as `f64`, so widening `0.7f32` would be written as
+/// `0.699999988079071`; these write the shortest decimal that reads back as
+/// the same `f32` instead.
+pub(crate) mod short_float {
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(value: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(widen(*value))
+    }
+
+    pub fn serialize_option<S: Serializer>(
+        value: &Option<f32>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&widen(*value)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    fn widen(value: f32) -> f64 {
+        value.to_string().parse().unwrap_or(value as f64)
+    }
+}
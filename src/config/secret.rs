@@ -0,0 +1,329 @@
+use crate::{Result, SkynetError};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::process::Command;
+use zeroize::Zeroize;
+
+/// Shown and saved in place of a secret value
+pub const REDACTED: &str = "********";
+
+/// Prefixes of secret references
+const REFERENCE_PREFIXES: &[&str] = &["env:", "file:", "cmd:"];
+
+/// A credential such as an API key.
+///
+/// The value never appears in `Debug` or `Display` output and is zeroed when
+/// dropped. In configuration files and the environment it may be given
+/// literally or as a reference:
+///
+/// - `env:VAR` reads the environment variable `VAR`
+/// - `file:/run/secrets/key` reads a file, without its trailing newline
+/// - `cmd:pass show skynet/key` runs a shell command and reads its output
+///
+/// Deserializing does not resolve references, so a configuration with a
+/// missing variable still loads; [`ConfigLoader::load`] resolves them and
+/// reports failures as configuration issues, and providers resolve any left
+/// when they are built.
+///
+/// Serializing writes the reference back, but never a resolved or literal
+/// value: those are written as [`REDACTED`], which is why
+/// [`Config::save_to_file`] refuses configurations with literal secrets.
+///
+/// [`ConfigLoader::load`]: crate::config::ConfigLoader::load
+/// [`Config::save_to_file`]: crate::config::Config::save_to_file
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret {
+    /// `None` until a reference is resolved
+    value: Option<String>,
+    /// Reference the value is resolved from
+    reference: Option<String>,
+}
+
+impl Secret {
+    /// A secret given literally
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: Some(value.into()),
+            reference: None,
+        }
+    }
+
+    /// Parse `input` as a reference, left unresolved, or as a literal value
+    pub fn parse(input: &str) -> Self {
+        if REFERENCE_PREFIXES
+            .iter()
+            .any(|prefix| input.starts_with(prefix))
+        {
+            Self::unresolved(input)
+        } else {
+            Self::new(input)
+        }
+    }
+
+    /// Parse `input` as a reference, resolving it, or as a literal value.
+    /// Resolving may block: it reads files and runs commands.
+    pub fn resolve(input: &str) -> Result<Self> {
+        let resolved = if let Some(var) = input.strip_prefix("env:") {
+            std::env::var(var).map_err(|e| {
+                SkynetError::config(format!("Failed to resolve {}", input)).with_source(e)
+            })?
+        } else if let Some(path) = input.strip_prefix("file:") {
+            let mut content = std::fs::read_to_string(path).map_err(|e| {
+                SkynetError::config(format!("Failed to resolve {}", input)).with_source(e)
+            })?;
+            let len = content.trim_end_matches(['\r', '\n']).len();
+            content.truncate(len);
+            content
+        } else if let Some(command) = input.strip_prefix("cmd:") {
+            run(input, command)?
+        } else {
+            return Ok(Self::new(input));
+        };
+        Ok(Self {
+            value: Some(resolved),
+            reference: Some(input.to_string()),
+        })
+    }
+
+    /// A reference that is not resolved yet; its value is empty until it is
+    pub fn unresolved(reference: impl Into<String>) -> Self {
+        Self {
+            value: None,
+            reference: Some(reference.into()),
+        }
+    }
+
+    /// This secret with its reference resolved, if it has one that is not
+    /// resolved yet. Resolving may block: it reads files and runs commands.
+    pub fn resolved(&self) -> Result<Self> {
+        match &self.reference {
+            Some(reference) if !self.is_resolved() => Self::resolve(reference),
+            _ => Ok(self.clone()),
+        }
+    }
+
+    /// Whether the value is known: it was given literally or its reference
+    /// has been resolved
+    pub fn is_resolved(&self) -> bool {
+        self.value.is_some() || self.reference.is_none()
+    }
+
+    /// The secret value itself; empty while the reference is unresolved
+    pub fn expose(&self) -> &str {
+        self.value.as_deref().unwrap_or_default()
+    }
+
+    /// The `env:`, `file:` or `cmd:` reference the value comes from
+    pub fn reference(&self) -> Option<&str> {
+        self.reference.as_deref()
+    }
+
+    /// Whether the value is known to be empty; an unresolved reference is
+    /// not
+    pub fn is_empty(&self) -> bool {
+        self.is_resolved() && self.expose().is_empty()
+    }
+
+    /// Whether a non-empty value was given literally, without a reference
+    pub fn is_literal(&self) -> bool {
+        self.reference.is_none() && !self.is_empty()
+    }
+}
+
+/// Output of `command` without its trailing newline, failing unless it
+/// exits successfully
+fn run(reference: &str, command: &str) -> Result<String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .map_err(|e| {
+            SkynetError::config(format!("Failed to resolve {}", reference)).with_source(e)
+        })?;
+    if !output.status.success() {
+        return Err(SkynetError::config(format!(
+            "Failed to resolve {}: command exited with {}",
+            reference, output.status
+        )));
+    }
+    let mut value = String::from_utf8(output.stdout).map_err(|e| {
+        SkynetError::config(format!(
+            "Failed to resolve {}: output is not UTF-8",
+            reference
+        ))
+        .with_source(e)
+    })?;
+    let len = value.trim_end_matches(['\r', '\n']).len();
+    value.truncate(len);
+    Ok(value)
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        if let Some(value) = &mut self.value {
+            value.zeroize();
+        }
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reference {
+            Some(reference) => write!(f, "Secret({})", reference),
+            None if self.is_empty() => f.write_str("Secret(\"\")"),
+            None => write!(f, "Secret({})", REDACTED),
+        }
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return Ok(());
+        }
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match &self.reference {
+            Some(reference) => serializer.serialize_str(reference),
+            None if self.is_empty() => serializer.serialize_str(""),
+            None => serializer.serialize_str(REDACTED),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let mut input = String::deserialize(deserializer)?;
+        let secret = Self::parse(&input);
+        input.zeroize();
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigLayer, ConfigLoader};
+    use crate::providers::scripted::ScriptedProvider;
+    use crate::providers::{ProviderConfig, ProviderRegistry};
+    use crate::Config;
+    use std::sync::{Arc, Mutex};
+
+    const MISSING: &str = "env:SKYNET_TEST_SECRET_THAT_IS_NOT_SET";
+
+    #[test]
+    fn deserializing_leaves_references_unresolved() {
+        let secret: Secret = serde_json::from_str(&format!("\"{}\"", MISSING)).unwrap();
+        assert!(!secret.is_resolved());
+        assert!(!secret.is_empty());
+        assert_eq!(secret.reference(), Some(MISSING));
+        assert_eq!(
+            serde_json::to_string(&secret).unwrap(),
+            format!("\"{}\"", MISSING)
+        );
+
+        let literal: Secret = serde_json::from_str("\"sk-test\"").unwrap();
+        assert!(literal.is_resolved());
+        assert_eq!(literal.expose(), "sk-test");
+    }
+
+    #[test]
+    fn resolves_file_references() {
+        let path = std::env::temp_dir().join(format!("skynet-secret-{}", std::process::id()));
+        std::fs::write(&path, "sk-from-file\n").unwrap();
+        let secret = Secret::parse(&format!("file:{}", path.display()));
+        let resolved = secret.resolved();
+        std::fs::remove_file(&path).unwrap();
+
+        let resolved = resolved.unwrap();
+        assert!(resolved.is_resolved());
+        assert_eq!(resolved.expose(), "sk-from-file");
+        assert_eq!(resolved.reference(), secret.reference());
+        assert!(Secret::parse(MISSING).resolved().is_err());
+    }
+
+    #[test]
+    fn reports_unresolvable_references_as_issues() {
+        let loader = ConfigLoader::empty()
+            .set("anthropic_api_key", MISSING)
+            .set("agent.temperature", "hot");
+        let (config, _) = loader.load_with_report().unwrap();
+        assert_eq!(config.anthropic_api_key.reference(), Some(MISSING));
+
+        let error = loader.load().unwrap_err().to_string();
+        assert!(error.contains("2 problems"), "{}", error);
+        assert!(error.contains(&format!("anthropic_api_key: Failed to resolve {}", MISSING)));
+        assert!(error.contains(&format!("[{}", ConfigLayer::CommandLine)));
+    }
+
+    #[test]
+    fn providers_resolve_their_key_when_built() {
+        let seen = Arc::new(Mutex::new(None));
+        let mut registry = ProviderRegistry::empty();
+        let recorded = Arc::clone(&seen);
+        registry.register("scripted", move |config| {
+            *recorded.lock().unwrap() = Some(config.api_key.clone());
+            Ok(ScriptedProvider::new("scripted").boxed())
+        });
+
+        let config = ProviderConfig {
+            provider_type: "scripted".to_string(),
+            api_key: Secret::parse("cmd:echo sk-from-command"),
+            model: "model".to_string(),
+            base_url: None,
+            max_tokens: None,
+            temperature: None,
+            context_window: None,
+            retry: None,
+        };
+        registry.create(&config).unwrap();
+        let key = seen.lock().unwrap().take().unwrap();
+        assert_eq!(key.expose(), "sk-from-command");
+
+        let config = ProviderConfig {
+            api_key: Secret::parse(MISSING),
+            ..config
+        };
+        assert!(registry.create(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn saving_refuses_literal_secrets() {
+        let path =
+            std::env::temp_dir().join(format!("skynet-secret-save-{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let config = Config {
+            anthropic_api_key: Secret::new("sk-literal"),
+            ..Config::default()
+        };
+        let error = config.save_to_file(path).await.unwrap_err().to_string();
+        assert!(error.contains("anthropic_api_key"), "{}", error);
+        assert!(!std::path::Path::new(path).exists());
+
+        let config = Config {
+            anthropic_api_key: Secret::parse(MISSING),
+            ..Config::default()
+        };
+        config.save_to_file(path).await.unwrap();
+        let saved = std::fs::read_to_string(path);
+        std::fs::remove_file(path).unwrap();
+        assert!(saved.unwrap().contains(MISSING));
+    }
+}
@@ -0,0 +1,554 @@
+use crate::config::secret::REDACTED;
+use crate::config::{
+    Config, ConfigReport, DatabaseConfig, LogRotation, LoggingConfig, MemoryBackend, Secret,
+    ValueOrigin,
+};
+use crate::providers::{ProviderConfig, RetryConfig};
+use std::fmt;
+use std::path::Path;
+
+/// API key written by `config init` in earlier versions, which must be
+/// replaced before use
+pub const PLACEHOLDER_API_KEY: &str = "your-api-key-here";
+
+/// Log levels accepted in `[logging] level`
+pub const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+
+/// Model name prefixes served by each provider type's public API.
+/// Providers with a custom `base_url`, and other types, accept any model.
+const KNOWN_MODEL_PREFIXES: &[(&str, &[&str])] = &[
+    ("anthropic", &["claude-"]),
+    ("openai", &["gpt-", "chatgpt-", "o1", "o3", "o4"]),
+];
+
+/// A problem found while validating a [`Config`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    /// Dotted key of the offending value, e.g. `agent.temperature`
+    pub key: String,
+    pub message: String,
+    /// Where the value was set, when known
+    pub origin: Option<ValueOrigin>,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)?;
+        if let Some(origin) = &self.origin {
+            write!(f, " [{}]", origin)?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects issues for one configuration
+#[derive(Default)]
+struct Issues(Vec<ConfigIssue>);
+
+impl Issues {
+    fn push(&mut self, key: impl Into<String>, message: impl Into<String>) {
+        self.0.push(ConfigIssue {
+            key: key.into(),
+            message: message.into(),
+            origin: None,
+        });
+    }
+
+    fn check(&mut self, ok: bool, key: impl Into<String>, message: impl Into<String>) {
+        if !ok {
+            self.push(key, message);
+        }
+    }
+}
+
+/// Every problem with `config`, each tagged with its origin in `report`,
+/// after the values `report` could not load
+pub(crate) fn issues(config: &Config, report: Option<&ConfigReport>) -> Vec<ConfigIssue> {
+    let mut issues = Issues::default();
+    check_config(config, &mut issues);
+    check_agent(config, &mut issues);
+    check_database(&config.database, &mut issues);
+    check_logging(&config.logging, &mut issues);
+
+    let Some(report) = report else {
+        return issues.0;
+    };
+    for issue in &mut issues.0 {
+        issue.origin = Some(report.origin(&issue.key));
+    }
+    report.issues().iter().cloned().chain(issues.0).collect()
+}
+
+fn check_config(config: &Config, issues: &mut Issues) {
+    match &config.provider {
+        None => {
+            let key = &config.anthropic_api_key;
+            if key.is_empty() {
+                issues.push(
+                    "anthropic_api_key",
+                    "is required; set it or ANTHROPIC_API_KEY",
+                );
+            } else if key.expose() == PLACEHOLDER_API_KEY {
+                issues.push(
+                    "anthropic_api_key",
+                    "is still the placeholder from `config init`; set a real key or ANTHROPIC_API_KEY",
+                );
+            } else if is_redacted(key) {
+                issues.push(
+                    "anthropic_api_key",
+                    "was redacted when the configuration was saved; set a real key or a reference",
+                );
+            }
+            check_model(
+                "default_model",
+                "anthropic",
+                None,
+                &config.default_model,
+                issues,
+            );
+        }
+        Some(name) => issues.check(
+            config.providers.contains_key(name),
+            "provider",
+            format!("'{}' is not defined in [providers]", name),
+        ),
+    }
+
+    for (name, provider) in &config.providers {
+        check_provider(name, provider, issues);
+    }
+
+    if let Some(fallback) = &config.fallback {
+        for name in &fallback.providers {
+            issues.check(
+                config.providers.contains_key(name),
+                "fallback.providers",
+                format!("'{}' is not defined in [providers]", name),
+            );
+        }
+        issues.check(
+            fallback.failure_threshold >= 1,
+            "fallback.failure_threshold",
+            "must be at least 1",
+        );
+    }
+
+    for (model, pricing) in &config.pricing {
+        let prices = [
+            pricing.input_per_mtok,
+            pricing.output_per_mtok,
+            pricing.cache_write_per_mtok,
+            pricing.cache_read_per_mtok,
+        ];
+        issues.check(
+            prices
+                .iter()
+                .all(|price| price.is_finite() && *price >= 0.0),
+            format!("pricing.{}", model),
+            "prices must be zero or more",
+        );
+    }
+}
+
+/// Whether `key` is the stand-in written for a literal secret
+fn is_redacted(key: &Secret) -> bool {
+    key.reference().is_none() && key.expose() == REDACTED
+}
+
+fn check_provider(name: &str, provider: &ProviderConfig, issues: &mut Issues) {
+    let key = |field: &str| format!("providers.{}.{}", name, field);
+
+    issues.check(
+        provider.api_key.expose() != PLACEHOLDER_API_KEY,
+        key("api_key"),
+        "is still a placeholder; set a real key",
+    );
+    issues.check(
+        !is_redacted(&provider.api_key),
+        key("api_key"),
+        "was redacted when the configuration was saved; set a real key or a reference",
+    );
+    check_model(
+        &key("model"),
+        &provider.provider_type,
+        provider.base_url.as_deref(),
+        &provider.model,
+        issues,
+    );
+    if let Some(base_url) = &provider.base_url {
+        let valid = reqwest::Url::parse(base_url)
+            .map(|url| matches!(url.scheme(), "http" | "https"))
+            .unwrap_or(false);
+        issues.check(
+            valid,
+            key("base_url"),
+            format!("'{}' is not an http(s) URL", base_url),
+        );
+    }
+    if let Some(max_tokens) = provider.max_tokens {
+        issues.check(max_tokens >= 1, key("max_tokens"), "must be at least 1");
+    }
+    if let Some(temperature) = provider.temperature {
+        check_temperature(
+            &key("temperature"),
+            &provider.provider_type,
+            temperature,
+            issues,
+        );
+    }
+    if let Some(context_window) = provider.context_window {
+        issues.check(
+            context_window >= 1,
+            key("context_window"),
+            "must be at least 1",
+        );
+    }
+    if let Some(retry) = &provider.retry {
+        check_retry(&key("retry"), retry, issues);
+    }
+}
+
+fn check_model(
+    key: &str,
+    provider_type: &str,
+    base_url: Option<&str>,
+    model: &str,
+    issues: &mut Issues,
+) {
+    if model.trim().is_empty() {
+        issues.push(key, "must name a model");
+        return;
+    }
+    if base_url.is_some() {
+        return;
+    }
+    let Some((_, prefixes)) = KNOWN_MODEL_PREFIXES
+        .iter()
+        .find(|(provider, _)| *provider == provider_type)
+    else {
+        return;
+    };
+    issues.check(
+        prefixes.iter().any(|prefix| model.starts_with(prefix)),
+        key,
+        format!(
+            "'{}' is not a known {} model (expected {})",
+            model,
+            provider_type,
+            prefixes
+                .iter()
+                .map(|prefix| format!("{}*", prefix))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    );
+}
+
+fn check_temperature(key: &str, provider_type: &str, temperature: f32, issues: &mut Issues) {
+    // Anthropic rejects temperatures above 1.0; OpenAI-style APIs allow 2.0
+    let max = if provider_type == "anthropic" {
+        1.0
+    } else {
+        2.0
+    };
+    issues.check(
+        temperature.is_finite() && (0.0..=max).contains(&temperature),
+        key,
+        format!("must be between 0.0 and {:.1} (got {})", max, temperature),
+    );
+}
+
+fn check_agent(config: &Config, issues: &mut Issues) {
+    let agent = &config.agent;
+    issues.check(
+        agent.max_context_messages >= 1,
+        "agent.max_context_messages",
+        "must be at least 1",
+    );
+    issues.check(
+        agent.heartbeat_interval_secs >= 1,
+        "agent.heartbeat_interval_secs",
+        "must be at least 1",
+    );
+    issues.check(
+        agent.max_tokens >= 1,
+        "agent.max_tokens",
+        "must be at least 1",
+    );
+    // The built-in provider is Anthropic; [providers] may be any type
+    let provider_type = if config.provider.is_none() {
+        "anthropic"
+    } else {
+        ""
+    };
+    check_temperature(
+        "agent.temperature",
+        provider_type,
+        agent.temperature,
+        issues,
+    );
+    issues.check(
+        agent.max_tool_iterations >= 1,
+        "agent.max_tool_iterations",
+        "must be at least 1",
+    );
+    issues.check(
+        agent.context_window_tokens > agent.max_tokens as usize,
+        "agent.context_window_tokens",
+        format!(
+            "must be larger than agent.max_tokens ({})",
+            agent.max_tokens
+        ),
+    );
+    if let Some(limit) = agent.spend_limit_usd {
+        issues.check(
+            limit.is_finite() && limit > 0.0,
+            "agent.spend_limit_usd",
+            format!("must be more than 0 (got {})", limit),
+        );
+    }
+    check_retry("agent.retry", &agent.retry, issues);
+
+    let compaction = &agent.compaction;
+    if compaction.enabled {
+        issues.check(
+            compaction.keep_recent < compaction.threshold_messages,
+            "agent.compaction.keep_recent",
+            format!(
+                "must be less than agent.compaction.threshold_messages ({})",
+                compaction.threshold_messages
+            ),
+        );
+    }
+}
+
+fn check_retry(key: &str, retry: &RetryConfig, issues: &mut Issues) {
+    issues.check(
+        retry.multiplier.is_finite() && retry.multiplier >= 1.0,
+        format!("{}.multiplier", key),
+        format!("must be at least 1.0 (got {})", retry.multiplier),
+    );
+    issues.check(
+        (0.0..=1.0).contains(&retry.jitter),
+        format!("{}.jitter", key),
+        format!("must be between 0.0 and 1.0 (got {})", retry.jitter),
+    );
+    issues.check(
+        retry.initial_backoff_ms <= retry.max_backoff_ms,
+        format!("{}.initial_backoff_ms", key),
+        format!(
+            "must not exceed {}.max_backoff_ms ({})",
+            key, retry.max_backoff_ms
+        ),
+    );
+}
+
+fn check_database(database: &DatabaseConfig, issues: &mut Issues) {
+    issues.check(
+        database.max_connections >= 1,
+        "database.max_connections",
+        "must be at least 1",
+    );
+    if database.backend == MemoryBackend::Sqlite && database.path != ":memory:" {
+        check_file_path("database.path", &database.path, issues);
+    }
+}
+
+fn check_logging(logging: &LoggingConfig, issues: &mut Issues) {
+    issues.check(
+        LOG_LEVELS.contains(&logging.level.to_lowercase().as_str()),
+        "logging.level",
+        format!(
+            "'{}' is not a log level (expected {})",
+            logging.level,
+            LOG_LEVELS.join(", ")
+        ),
+    );
+    match &logging.log_file {
+        Some(path) => check_file_path("logging.log_file", path, issues),
+        None => issues.check(
+            !logging.file_logging,
+            "logging.file_logging",
+            "is true but logging.log_file is not set",
+        ),
+    }
+    issues.check(
+        logging.max_size_bytes != Some(0),
+        "logging.max_size_bytes",
+        "must be at least 1",
+    );
+    issues.check(
+        logging.max_size_bytes.is_none() || logging.rotation == LogRotation::Never,
+        "logging.max_size_bytes",
+        "cannot be combined with a time rotation; set logging.rotation = \"never\"",
+    );
+    issues.check(
+        logging.max_log_files != Some(0),
+        "logging.max_log_files",
+        "must be at least 1",
+    );
+}
+
+/// `path` must name a file, not a directory, in a directory that exists
+fn check_file_path(key: &str, path: &str, issues: &mut Issues) {
+    if path.trim().is_empty() {
+        issues.push(key, "must not be empty");
+        return;
+    }
+    let path = Path::new(path);
+    if path.is_dir() {
+        issues.push(key, format!("{} is a directory", path.display()));
+        return;
+    }
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    issues.check(
+        parent.is_dir(),
+        key,
+        format!("directory {} does not exist", parent.display()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigLayer, ConfigLoader};
+
+    fn load(loader: ConfigLoader) -> Vec<ConfigIssue> {
+        let (config, report) = loader
+            .env([("ANTHROPIC_API_KEY", "sk-ant-test")])
+            .load_with_report()
+            .unwrap();
+        issues(&config, Some(&report))
+    }
+
+    fn origin(layer: ConfigLayer, location: &str) -> Option<ValueOrigin> {
+        Some(ValueOrigin {
+            layer,
+            location: Some(location.to_string()),
+        })
+    }
+
+    #[test]
+    fn reports_every_value_of_the_wrong_type() {
+        let issues = load(
+            ConfigLoader::empty()
+                .set("agent.max_tokens", "lots")
+                .set("agent.temperature", "hot"),
+        );
+        assert_eq!(
+            issues,
+            [
+                ConfigIssue {
+                    key: "agent.max_tokens".to_string(),
+                    message: "invalid type: string \"lots\", expected an integer".to_string(),
+                    origin: origin(ConfigLayer::CommandLine, "--set agent.max_tokens=lots"),
+                },
+                ConfigIssue {
+                    key: "agent.temperature".to_string(),
+                    message: "invalid type: string \"hot\", expected a floating point".to_string(),
+                    origin: origin(ConfigLayer::CommandLine, "--set agent.temperature=hot"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_load_errors_with_validation_errors() {
+        let loader = ConfigLoader::empty()
+            .env([
+                ("SKYNET_TEMPERATURE", "hot"),
+                ("SKYNET_MAX_TOKENS", "lots"),
+                ("SKYNET_DB_BACKEND", "mongo"),
+                ("SKYNET_MAX_TOOL_ITERATIONS", "0"),
+                ("ANTHROPIC_API_KEY", "sk-ant-test"),
+            ])
+            .set("logging.level", "loud");
+        let (config, report) = loader.load_with_report().unwrap();
+        let issues = issues(&config, Some(&report));
+
+        let keys: Vec<&str> = issues.iter().map(|issue| issue.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "agent.max_tokens",
+                "agent.temperature",
+                "database.backend",
+                "agent.max_tool_iterations",
+                "logging.level",
+            ]
+        );
+        assert!(issues[2].message.contains("mongo"));
+        assert_eq!(
+            issues[2].origin,
+            origin(ConfigLayer::Environment, "SKYNET_DB_BACKEND")
+        );
+        assert_eq!(
+            issues[3].origin,
+            origin(ConfigLayer::Environment, "SKYNET_MAX_TOOL_ITERATIONS")
+        );
+        assert_eq!(
+            issues[4].origin,
+            origin(ConfigLayer::CommandLine, "--set logging.level=loud")
+        );
+
+        let error = loader.load().unwrap_err().to_string();
+        assert!(error.contains("5 problems"), "{}", error);
+    }
+
+    #[test]
+    fn reports_a_table_missing_a_required_field() {
+        let issues = load(
+            ConfigLoader::empty()
+                .set("providers.local.model", "llama3")
+                .set("logging.format", "xml"),
+        );
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+        let provider = issues
+            .iter()
+            .find(|issue| issue.key == "providers.local")
+            .unwrap();
+        assert!(provider.message.contains("missing field `type`"));
+        assert_eq!(
+            provider.origin,
+            origin(
+                ConfigLayer::CommandLine,
+                "--set providers.local.model=llama3"
+            )
+        );
+        assert!(issues.iter().any(|issue| issue.key == "logging.format"));
+    }
+
+    #[test]
+    fn dropped_values_fall_back_to_their_defaults() {
+        let (config, report) = ConfigLoader::empty()
+            .set("agent.temperature", "hot")
+            .set("agent.max_tokens", "512")
+            .load_with_report()
+            .unwrap();
+        assert_eq!(
+            config.agent.temperature,
+            Config::default().agent.temperature
+        );
+        assert_eq!(config.agent.max_tokens, 512);
+        assert_eq!(
+            report.origin("agent.temperature").layer,
+            ConfigLayer::Defaults
+        );
+    }
+
+    #[test]
+    fn tags_validation_issues_without_a_report() {
+        let mut config = Config {
+            anthropic_api_key: Secret::new("sk-ant-test".to_string()),
+            ..Config::default()
+        };
+        config.agent.max_tokens = 0;
+        config.logging.level = "loud".to_string();
+        let issues = issues(&config, None);
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+        assert!(issues.iter().all(|issue| issue.origin.is_none()));
+    }
+}
@@ -0,0 +1,251 @@
+use crate::config::loader::flatten;
+use crate::config::{Config, ConfigLoader, Secret};
+use crate::{Result, SkynetError};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Time for a burst of file events to settle; editors often save in steps
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Keys a running agent applies without a restart
+const RELOADABLE_KEYS: &[&str] = &[
+    "default_model",
+    "agent.max_tokens",
+    "agent.temperature",
+    "agent.max_context_messages",
+    "agent.context_window_tokens",
+    "agent.context_search_results",
+    "agent.max_tool_iterations",
+    "agent.heartbeat_interval_secs",
+    "logging.level",
+    "tools.allowed",
+    "tools.denied",
+];
+
+/// Fields of the active `[providers.<name>]` entry applied without a restart
+const RELOADABLE_PROVIDER_FIELDS: &[&str] =
+    &["model", "max_tokens", "temperature", "context_window"];
+
+/// Dotted keys whose values differ between two configurations
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigChanges {
+    /// Changes a running agent picks up
+    pub reloadable: Vec<String>,
+    /// Changes that only take effect after a restart
+    pub restart_required: Vec<String>,
+}
+
+impl ConfigChanges {
+    /// Compare `old` with `new`, classifying keys by whether `new` can be
+    /// applied to a running agent
+    pub fn between(old: &Config, new: &Config) -> Result<Self> {
+        let old_values: BTreeMap<String, serde_json::Value> = flatten(old)?.into_iter().collect();
+        let new_values: BTreeMap<String, serde_json::Value> = flatten(new)?.into_iter().collect();
+
+        let keys: BTreeSet<&String> = old_values.keys().chain(new_values.keys()).collect();
+        let mut changed: BTreeSet<String> = keys
+            .into_iter()
+            .filter(|key| old_values.get(*key) != new_values.get(*key))
+            .cloned()
+            .collect();
+
+        // Secrets serialize redacted, so compare their values directly
+        let old_secrets = secrets(old);
+        for (key, secret) in secrets(new) {
+            if old_secrets.get(&key) != Some(&secret) {
+                changed.insert(key);
+            }
+        }
+
+        let mut changes = Self::default();
+        for key in changed {
+            if is_reloadable(&key, new) {
+                changes.reloadable.push(key);
+            } else {
+                changes.restart_required.push(key);
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.reloadable.is_empty() && self.restart_required.is_empty()
+    }
+}
+
+/// Whether a change to `key` can be applied to an agent running with
+/// `config`'s active provider
+pub fn is_reloadable(key: &str, config: &Config) -> bool {
+    if RELOADABLE_KEYS.contains(&key) {
+        return true;
+    }
+    let (Some(active), Some(rest)) = (&config.provider, key.strip_prefix("providers.")) else {
+        return false;
+    };
+    rest.strip_prefix(active.as_str())
+        .and_then(|field| field.strip_prefix('.'))
+        .is_some_and(|field| RELOADABLE_PROVIDER_FIELDS.contains(&field))
+}
+
+/// Every secret in `config`, by dotted key
+fn secrets(config: &Config) -> BTreeMap<String, &Secret> {
+    let providers = config
+        .providers
+        .iter()
+        .map(|(name, provider)| (format!("providers.{}.api_key", name), &provider.api_key));
+    std::iter::once(("anthropic_api_key".to_string(), &config.anthropic_api_key))
+        .chain(providers)
+        .collect()
+}
+
+/// Watches the files a [`ConfigLoader`] reads and publishes every valid,
+/// changed configuration.
+///
+/// Changes that fail to load or validate are logged and ignored, leaving the
+/// previous configuration in effect. Changes that need a restart are
+/// published too, with a warning naming each key; subscribers only apply
+/// the [reloadable](is_reloadable) settings. Watching stops when the watcher
+/// is dropped.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+    task: JoinHandle<()>,
+}
+
+impl ConfigWatcher {
+    /// Start watching, with `current` as the configuration in effect
+    pub fn spawn(
+        loader: ConfigLoader,
+        current: Config,
+    ) -> Result<(Self, watch::Receiver<Arc<Config>>)> {
+        let files: Vec<(PathBuf, OsString)> = loader
+            .files()
+            .iter()
+            .filter_map(|path| Some((directory(path), path.file_name()?.to_os_string())))
+            .filter(|(dir, _)| dir.is_dir())
+            .collect();
+
+        let (events_sender, mut events) = mpsc::unbounded_channel();
+        let watched = files.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                // Reading the files on reload raises access events; skip them
+                Ok(event) if event.kind.is_access() => {}
+                Ok(event) if event.paths.iter().any(|path| is_watched(path, &watched)) => {
+                    let _ = events_sender.send(());
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Configuration watcher error: {}", e),
+            })
+            .map_err(|e| {
+                SkynetError::config("Failed to watch configuration files").with_source(e)
+            })?;
+
+        let directories: BTreeSet<&PathBuf> = files.iter().map(|(dir, _)| dir).collect();
+        for dir in directories {
+            watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .map_err(|e| {
+                    SkynetError::config(format!("Failed to watch {}", dir.display())).with_source(e)
+                })?;
+            debug!("Watching {} for configuration changes", dir.display());
+        }
+
+        let (updates_sender, updates) = watch::channel(Arc::new(current.clone()));
+        let task = tokio::spawn(async move {
+            let mut current = current;
+            while events.recv().await.is_some() {
+                tokio::time::sleep(DEBOUNCE).await;
+                while events.try_recv().is_ok() {}
+
+                // Loading reads files and may run `cmd:` secret commands
+                let reloaded = tokio::task::spawn_blocking({
+                    let loader = loader.clone();
+                    let current = current.clone();
+                    move || reload(&loader, &current)
+                })
+                .await;
+                match reloaded {
+                    Ok(Some(config)) => {
+                        current = config.clone();
+                        if updates_sender.send(Arc::new(config)).is_err() {
+                            return;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!("Ignoring configuration change: {}", e),
+                }
+            }
+        });
+
+        Ok((
+            Self {
+                _watcher: watcher,
+                task,
+            },
+            updates,
+        ))
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Load the configuration again, returning it if it is valid and changed
+fn reload(loader: &ConfigLoader, current: &Config) -> Option<Config> {
+    let config = match loader.load() {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("Ignoring configuration change: {}", e);
+            return None;
+        }
+    };
+    let changes = match ConfigChanges::between(current, &config) {
+        Ok(changes) => changes,
+        Err(e) => {
+            warn!("Ignoring configuration change: {}", e);
+            return None;
+        }
+    };
+    if changes.is_empty() {
+        debug!("Configuration files changed but the configuration did not");
+        return None;
+    }
+
+    for key in &changes.restart_required {
+        warn!("Configuration {} changed; restart to apply it", key);
+    }
+    if !changes.reloadable.is_empty() {
+        info!("Reloading configuration: {}", changes.reloadable.join(", "));
+    }
+    Some(config)
+}
+
+/// Canonical directory holding `path`
+fn directory(path: &Path) -> PathBuf {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+fn is_watched(path: &Path, files: &[(PathBuf, OsString)]) -> bool {
+    let Some(name) = path.file_name() else {
+        return false;
+    };
+    let dir = directory(path);
+    files
+        .iter()
+        .any(|(watched_dir, watched_name)| watched_name == name && *watched_dir == dir)
+}
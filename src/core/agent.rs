@@ -1,10 +1,26 @@
-use crate::{Config, Result};
-use crate::core::message::Message;
-use crate::providers::LLMProvider;
+use crate::config::ToolsConfig;
+use crate::core::channel::{AgentHandle, Input, LogSink, OutputSink, INPUT_QUEUE_CAPACITY};
+use crate::core::compaction::Compactor;
+use crate::core::context::{self, ContextBuilder, ContextWindow};
+use crate::core::message::{Message, Role, ToolCall};
+use crate::core::schema;
+use crate::core::session::{Session, SessionManager, DEFAULT_SESSION_ID};
+use crate::core::usage::{UsageReport, UsageTracker};
+use crate::memory::search::{self, SearchHit, SearchQuery};
+use crate::providers::fallback::PROVIDER_METADATA_KEY;
+use crate::providers::ProviderError;
+use crate::providers::{
+    CompletionRequest, GenerationOptions, LLMProvider, ProviderConfig, ProviderRegistry,
+    StreamAccumulator, StreamEvent, ToolDefinition,
+};
+use crate::{Config, Result, SkynetError};
 use async_trait::async_trait;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{info, debug, error};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock as SyncRwLock};
+use tokio::sync::{mpsc, watch, RwLock};
+use tracing::{debug, error, info, instrument, warn};
 
 /// Tool execution trait
 #[async_trait]
@@ -12,170 +28,814 @@ pub trait Tool: Send + Sync {
     async fn execute(&self, args: serde_json::Value) -> Result<String>;
     fn name(&self) -> &str;
     fn description(&self) -> &str;
+
+    /// JSON Schema describing the arguments `execute` expects.
+    ///
+    /// Arguments are validated against this schema before `execute` runs.
+    fn input_schema(&self) -> serde_json::Value;
+
+    /// Describe this tool for the LLM provider
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: self.name().to_string(),
+            description: self.description().to_string(),
+            input_schema: self.input_schema(),
+        }
+    }
 }
 
 /// Memory storage trait
 #[async_trait]
 pub trait Memory: Send + Sync {
     async fn store(&self, message: &Message) -> Result<()>;
-    async fn retrieve(&self, limit: usize) -> Result<Vec<Message>>;
+
+    /// Most recent `limit` messages of a session, oldest first
+    async fn retrieve(&self, session_id: &str, limit: usize) -> Result<Vec<Message>>;
+
+    /// Messages of every session containing `query`
     async fn search(&self, query: &str) -> Result<Vec<Message>>;
+
+    /// Replace the messages listed in `summary`'s `summary_of` metadata with
+    /// `summary`, so `retrieve` returns the summary in their place.
+    ///
+    /// Persistent backends keep the originals, still reachable through
+    /// [`get`](Self::get) and search; volatile ones may discard them.
+    async fn compact(&self, summary: &Message) -> Result<()>;
+
+    /// Create or update a session record
+    async fn save_session(&self, session: &Session) -> Result<()>;
+
+    /// All session records
+    async fn sessions(&self) -> Result<Vec<Session>>;
+
+    /// Delete a session record and every message in it
+    async fn delete_session(&self, id: &str) -> Result<()>;
+
+    /// Delete messages older than `before`, in one session or in all of
+    /// them, returning how many were deleted
+    async fn prune(&self, before: DateTime<Utc>, session_id: Option<&str>) -> Result<usize>;
+
+    /// Look up a session record by ID
+    async fn session(&self, id: &str) -> Result<Option<Session>> {
+        Ok(self
+            .sessions()
+            .await?
+            .into_iter()
+            .find(|session| session.id == id))
+    }
+
+    /// Look up a message by ID, including one replaced by a summary
+    async fn get(&self, id: &str) -> Result<Option<Message>> {
+        Ok(self
+            .search("")
+            .await?
+            .into_iter()
+            .find(|message| message.id == id))
+    }
+
+    /// Ranked search with role, type and time filters.
+    ///
+    /// The default implementation filters the results of [`search`](Self::search)
+    /// and gives every hit the same score (or, with `match_any`, the number of
+    /// terms it matched); backends with a real index should override it.
+    async fn search_with(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        let texts: Vec<String> = if query.match_any {
+            search::parse_terms(&query.text)
+                .into_iter()
+                .map(|term| term.text().to_string())
+                .collect()
+        } else {
+            vec![query.text.clone()]
+        };
+
+        let mut hits: Vec<SearchHit> = Vec::new();
+        for text in &texts {
+            for message in self.search(text).await? {
+                if !query.matches_filters(&message) {
+                    continue;
+                }
+                match hits.iter_mut().find(|hit| hit.message.id == message.id) {
+                    Some(hit) => hit.score += 1.0,
+                    None => hits.push(SearchHit {
+                        snippet: search::excerpt(&message.content),
+                        score: 1.0,
+                        message,
+                    }),
+                }
+            }
+        }
+
+        // Stable sort keeps the backend's order among equal scores
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(query.limit.unwrap_or(usize::MAX));
+        Ok(hits)
+    }
+}
+
+/// Settings read at the start of every turn, replaced as a whole when the
+/// configuration is reloaded
+#[derive(Debug, Clone)]
+struct TurnSettings {
+    max_context_messages: usize,
+    context_search_results: usize,
+    max_tool_iterations: usize,
+    /// Tokens available for the context, after the response's share
+    context_budget: usize,
+    /// Generation options that differ from those the provider was built with
+    generation: GenerationOptions,
+    tools: ToolsConfig,
+}
+
+impl TurnSettings {
+    /// Settings for `config`, where `built` is the configuration the
+    /// provider was created from and `active` the provider's current one
+    fn new(config: &Config, built: &ProviderConfig, active: &ProviderConfig) -> Self {
+        let agent = &config.agent;
+        let max_tokens = active.max_tokens.unwrap_or(agent.max_tokens);
+        let changed = |current: &ProviderConfig| GenerationOptions {
+            model: (current.model != built.model).then(|| current.model.clone()),
+            max_tokens: current
+                .max_tokens
+                .filter(|_| current.max_tokens != built.max_tokens),
+            temperature: current
+                .temperature
+                .filter(|_| current.temperature != built.temperature),
+            ..GenerationOptions::default()
+        };
+
+        Self {
+            max_context_messages: agent.max_context_messages,
+            context_search_results: agent.context_search_results,
+            max_tool_iterations: agent.max_tool_iterations.max(1),
+            // Leave room in the context window for the response
+            context_budget: active
+                .context_window
+                .unwrap_or(agent.context_window_tokens)
+                .saturating_sub(max_tokens as usize),
+            generation: changed(active),
+            tools: config.tools.clone(),
+        }
+    }
 }
 
 /// Main SKYNET agent implementation
 pub struct SkynetAgent {
-    config: Config,
     provider: Box<dyn LLMProvider>,
+    /// Name and configuration the active provider was created with
+    provider_name: Option<String>,
+    provider_config: ProviderConfig,
+    settings: SyncRwLock<Arc<TurnSettings>>,
+    config_updates: Option<watch::Receiver<Arc<Config>>>,
     tools: Vec<Arc<dyn Tool>>,
     memory: Arc<dyn Memory>,
-    conversation: Arc<RwLock<Vec<Message>>>,
+    sessions: SessionManager,
     running: Arc<RwLock<bool>>,
+    usage: Arc<UsageTracker>,
+    compactor: Compactor,
+    inbox: Option<mpsc::Receiver<Input>>,
+    inbox_sender: mpsc::Sender<Input>,
+    output: Arc<dyn OutputSink>,
+    model: Option<String>,
 }
 
 impl SkynetAgent {
-    /// Create a new SKYNET agent
+    /// Create a new SKYNET agent using the built-in provider types
     pub async fn new(config: Config) -> Result<Self> {
+        Self::with_registry(config, &ProviderRegistry::default()).await
+    }
+
+    /// Create a new SKYNET agent, resolving the configured provider through
+    /// `registry` so custom provider types can be used
+    pub async fn with_registry(config: Config, registry: &ProviderRegistry) -> Result<Self> {
         info!("Initializing SKYNET agent...");
 
-        // Initialize provider (TODO: make configurable)
-        let provider = crate::providers::anthropic::AnthropicProvider::new(
-            config.anthropic_api_key.clone()
-        )?;
+        // Initialize provider
+        let provider = registry.create_from_config(&config)?;
 
-        // Initialize memory (TODO: implement proper memory storage)
-        let memory = Arc::new(InMemoryStorage::new());
+        let active = config.active_provider()?;
+        let settings = TurnSettings::new(&config, &active, &active);
+
+        // Initialize memory
+        let memory = crate::memory::from_config(&config.database)?;
+        let sessions = SessionManager::new(Arc::clone(&memory));
+        sessions.ensure_default().await?;
 
         // Initialize tools (empty for now)
         let tools = Vec::new();
 
+        let usage = Arc::new(UsageTracker::from_config(&config));
+        let compactor = Compactor::new(config.agent.compaction.clone());
+        let (inbox_sender, inbox) = mpsc::channel(INPUT_QUEUE_CAPACITY);
+
         Ok(Self {
-            config,
-            provider: Box::new(provider),
+            provider,
+            provider_name: config.provider.clone(),
+            provider_config: active,
+            settings: SyncRwLock::new(Arc::new(settings)),
+            config_updates: None,
             tools,
             memory,
-            conversation: Arc::new(RwLock::new(Vec::new())),
+            sessions,
             running: Arc::new(RwLock::new(false)),
+            usage,
+            compactor,
+            inbox: Some(inbox),
+            inbox_sender,
+            output: Arc::new(LogSink),
+            model: None,
         })
     }
 
-    /// Start the main agent loop
+    /// Send replies to `output` instead of the log
+    pub fn with_output(mut self, output: Arc<dyn OutputSink>) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Apply each configuration published on `updates` while the loop runs,
+    /// as with [`apply_config`](Self::apply_config)
+    pub fn with_config_updates(mut self, updates: watch::Receiver<Arc<Config>>) -> Self {
+        self.config_updates = Some(updates);
+        self
+    }
+
+    /// Apply the reloadable settings of `config` to the following turns:
+    /// the active provider's model, `max_tokens`, `temperature` and context
+    /// window, the `[agent]` context and tool limits, and the `[tools]`
+    /// permissions.
+    ///
+    /// Turns already in flight finish with the settings they started with.
+    /// Everything else in `config`, including which provider is active,
+    /// only takes effect when the agent is created again.
+    pub fn apply_config(&self, config: &Config) -> Result<()> {
+        let active = config.resolve_provider(self.provider_name.as_deref())?;
+        let settings = TurnSettings::new(config, &self.provider_config, &active);
+        debug!("Applying settings: {:?}", settings);
+        *self
+            .settings
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(settings);
+        info!("Configuration applied to the agent");
+        Ok(())
+    }
+
+    /// Settings for a turn starting now
+    fn settings(&self) -> Arc<TurnSettings> {
+        Arc::clone(
+            &self
+                .settings
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        )
+    }
+
+    /// Handle for sending messages to this agent's loop
+    pub fn handle(&self) -> AgentHandle {
+        AgentHandle::new(self.inbox_sender.clone())
+    }
+
+    /// Register a tool the agent may call
+    pub fn register_tool(&mut self, tool: Arc<dyn Tool>) {
+        info!("Registered tool: {}", tool.name());
+        self.tools.push(tool);
+    }
+
+    /// Tools the agent may call: those registered and permitted by the
+    /// `[tools]` configuration
+    pub fn tools(&self) -> Vec<ToolDefinition> {
+        self.permitted_tools(&self.settings())
+            .map(|tool| tool.definition())
+            .collect()
+    }
+
+    fn permitted_tools<'a>(
+        &'a self,
+        settings: &'a TurnSettings,
+    ) -> impl Iterator<Item = &'a Arc<dyn Tool>> + 'a {
+        self.tools
+            .iter()
+            .filter(|tool| settings.tools.permits(tool.name()))
+    }
+
+    /// Model requests are sent to
+    pub fn model(&self) -> String {
+        self.model
+            .clone()
+            .or_else(|| self.settings().generation.model.clone())
+            .unwrap_or_else(|| self.provider.model().to_string())
+    }
+
+    /// Send requests to `model` instead of the configured model; `None`
+    /// restores it
+    pub fn set_model(&mut self, model: Option<String>) {
+        self.model = model;
+        info!("Model set to {}", self.model());
+    }
+
+    /// ID of the session messages without a session ID are added to
+    pub fn session_id(&self) -> &str {
+        DEFAULT_SESSION_ID
+    }
+
+    /// Manager for creating, listing, forking, archiving and deleting sessions
+    pub fn sessions(&self) -> SessionManager {
+        self.sessions.clone()
+    }
+
+    /// Token usage and cost so far, overall, per session and per provider
+    pub fn usage_report(&self) -> UsageReport {
+        self.usage.report()
+    }
+
+    /// Start the main agent loop.
+    ///
+    /// Waits for messages sent through an [`AgentHandle`] and answers them,
+    /// delivering replies to the output sink. Sessions are served
+    /// concurrently; messages within a session are answered one at a time,
+    /// in order. Returns once stopped via [`AgentHandle::stop`] or
+    /// [`stop`](Self::stop), or on a fatal error.
     pub async fn run(&mut self) -> Result<()> {
+        let Some(mut inbox) = self.inbox.take() else {
+            return Err(SkynetError::agent("Agent loop is already running"));
+        };
         info!("🚀 Starting SKYNET agent loop");
-        
+
         // Set running state
         {
             let mut running = self.running.write().await;
             *running = true;
         }
 
-        // Main agent loop
+        let mut updates = self.config_updates.take();
+        let result = self.serve(&mut inbox, &mut updates).await;
+
+        self.inbox = Some(inbox);
+        self.config_updates = updates;
+        *self.running.write().await = false;
+        info!("SKYNET agent loop terminated");
+        result
+    }
+
+    /// Dispatch queued messages until stopped, keeping at most one turn per
+    /// session in flight and applying configuration updates between turns
+    async fn serve(
+        &self,
+        inbox: &mut mpsc::Receiver<Input>,
+        updates: &mut Option<watch::Receiver<Arc<Config>>>,
+    ) -> Result<()> {
+        let mut in_flight = FuturesUnordered::new();
+        // Sessions with a turn in flight, and the messages waiting behind it
+        let mut busy: HashMap<String, VecDeque<Message>> = HashMap::new();
+        let mut accepting = true;
+
         loop {
             // Check if we should continue running
-            {
-                let running = self.running.read().await;
-                if !*running {
-                    break;
-                }
+            if !*self.running.read().await {
+                accepting = false;
+            }
+            if !accepting && in_flight.is_empty() {
+                return Ok(());
             }
 
-            // Agent loop steps:
-            match self.process_cycle().await {
-                Ok(_) => debug!("Agent cycle completed successfully"),
-                Err(e) => {
-                    error!("Agent cycle error: {}", e);
-                    // Continue running unless it's a fatal error
+            tokio::select! {
+                // 1. Wait for the next message
+                input = inbox.recv(), if accepting => match input {
+                    Some(Input::Message(message)) => {
+                        let session = message.session().to_string();
+                        match busy.get_mut(&session) {
+                            Some(waiting) => waiting.push_back(message),
+                            None => {
+                                busy.insert(session, VecDeque::new());
+                                in_flight.push(self.turn(message));
+                            }
+                        }
+                    }
+                    Some(Input::Stop) | None => accepting = false,
+                },
+                Some(config) = next_config(updates) => {
+                    if let Err(e) = self.apply_config(&config) {
+                        warn!("Keeping the previous settings: {}", e);
+                    }
                 }
-            }
+                Some((session, result)) = in_flight.next() => {
+                    match result {
+                        Ok(_) => debug!("Agent cycle completed successfully"),
+                        Err(e) if e.is_retryable() => {
+                            warn!("Agent cycle failed with a transient error: {}", e);
+                        }
+                        Err(e) if is_fatal(&e) => {
+                            error!("Fatal agent error, stopping: {}", e);
+                            return Err(e);
+                        }
+                        Err(e) => {
+                            error!("Agent cycle error: {}", e);
+                        }
+                    }
 
-            // Sleep between cycles to prevent busy waiting
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                    match busy.get_mut(&session).and_then(VecDeque::pop_front) {
+                        Some(next) => in_flight.push(self.turn(next)),
+                        None => {
+                            busy.remove(&session);
+                        }
+                    }
+                }
+            }
         }
+    }
 
-        info!("SKYNET agent loop terminated");
-        Ok(())
+    /// Answer `input`, replying with an error message if that fails. Returns
+    /// the session served and the outcome.
+    async fn turn(&self, input: Message) -> (String, Result<()>) {
+        let session = input.session().to_string();
+        let result = self.respond(input.clone()).await.map(|_| ());
+        if let Err(e) = &result {
+            self.deliver(
+                Message::error(e.to_string())
+                    .with_session(&session)
+                    .in_reply_to(&input.id),
+            )
+            .await;
+        }
+        (session, result)
     }
 
-    /// Process a single agent cycle
-    async fn process_cycle(&self) -> Result<()> {
-        // 1. Receive message (placeholder - would come from queue/channel)
-        // For now, we'll just process a test message
-        let input_message = Message::user("Hello, SKYNET!".to_string());
+    /// Answer a single message right away, without going through the loop.
+    ///
+    /// The reply is delivered to the output sink, streamed if it asks for
+    /// deltas, and returned. Events logged meanwhile carry the session in a
+    /// `turn` span.
+    #[instrument(name = "turn", skip_all, fields(session = %input.session(), message_id = %input.id))]
+    pub async fn respond(&self, input: Message) -> Result<Message> {
+        let session = self.sessions.open(input.session()).await?;
+        // Timestamp queued input when its turn starts so it sorts after the
+        // previous turn's reply
+        let mut input_message = input.with_session(&session.id);
+        input_message.timestamp = chrono::Utc::now();
+        let input_message = &input_message;
+        let settings = self.settings();
 
         // 2. Load context from memory
-        let context = self.memory.retrieve(10).await?;
-        debug!("Loaded {} messages from context", context.len());
+        let context = self.build_context(input_message, &settings).await?;
+        debug!(
+            "Loaded {} messages (~{} tokens) into context",
+            context.messages.len(),
+            context.estimated_tokens
+        );
 
         // 3. Build conversation for LLM
-        let mut conversation = context;
-        conversation.push(input_message.clone());
+        let conversation = context.messages;
 
         // Store input in memory
-        self.memory.store(&input_message).await?;
-
-        // 4. Call LLM provider
-        let response = self.provider.generate(&conversation).await?;
-        debug!("LLM response: {}", response);
+        self.memory.store(input_message).await?;
 
-        // 5. Execute any tools (placeholder)
-        // TODO: Parse tool calls from LLM response and execute them
+        // 4. Call LLM provider, executing tools until it gives a final answer
+        let response_message = self
+            .run_tool_loop(conversation, input_message, &settings)
+            .await?;
+        debug!("LLM response: {}", response_message.content);
 
-        // 6. Store response in memory
-        let response_message = Message::assistant(response);
-        self.memory.store(&response_message).await?;
+        // 5. Send response to the output sink
+        let reply = response_message.in_reply_to(&input_message.id);
+        self.deliver(reply.clone()).await;
 
-        // 7. Send response (placeholder - would send to output channel)
-        info!("Agent response: {}", response_message.content);
+        // 6. Summarize old history once it grows past the threshold
+        if let Err(e) = self.compact_history(&session.id).await {
+            warn!("History compaction failed: {}", e);
+        }
 
-        Ok(())
+        Ok(reply)
     }
 
-    /// Gracefully stop the agent
-    pub async fn stop(&self) {
-        let mut running = self.running.write().await;
-        *running = false;
-        info!("SKYNET agent stop requested");
+    /// Hand `reply` to the output sink; a failing sink does not stop the agent
+    async fn deliver(&self, reply: Message) {
+        if let Err(e) = self.output.deliver(&reply).await {
+            warn!("Failed to deliver reply {}: {}", reply.id, e);
+        }
     }
-}
 
-/// Simple in-memory storage implementation for testing
-struct InMemoryStorage {
-    messages: Arc<RwLock<Vec<Message>>>,
-}
+    /// Assemble the conversation for `input` within the context budget from
+    /// pinned system messages, recent turns and relevant older messages
+    async fn build_context(
+        &self,
+        input: &Message,
+        settings: &TurnSettings,
+    ) -> Result<ContextWindow> {
+        let session = input.session();
+        let history = self
+            .memory
+            .retrieve(session, settings.max_context_messages)
+            .await?;
 
-impl InMemoryStorage {
-    fn new() -> Self {
-        Self {
-            messages: Arc::new(RwLock::new(Vec::new())),
+        let mut system: Vec<Message> = self
+            .memory
+            .search_with(
+                &SearchQuery::new("")
+                    .in_session(session)
+                    .with_roles(vec![Role::System])
+                    .active_only(),
+            )
+            .await?
+            .into_iter()
+            .map(|hit| hit.message)
+            .collect();
+        system.sort_by_key(|message| message.timestamp);
+
+        let keywords = context::keywords(&input.content);
+        let hits = if keywords.is_empty() || settings.context_search_results == 0 {
+            Vec::new()
+        } else {
+            // Over-fetch since hits already in the window are skipped
+            let query = SearchQuery::new(keywords)
+                .in_session(session)
+                .matching_any()
+                .with_limit(settings.context_search_results + history.len());
+            self.memory.search_with(&query).await?
+        };
+
+        let window = ContextBuilder::new(settings.context_budget)
+            .with_max_messages(settings.max_context_messages)
+            .with_max_recalled(settings.context_search_results)
+            .build(&system, &history, &hits, input);
+        if !window.dropped.is_empty() {
+            debug!(
+                "Dropped {} older messages that did not fit the context",
+                window.dropped.len()
+            );
         }
+        Ok(window)
     }
-}
 
-#[async_trait]
-impl Memory for InMemoryStorage {
-    async fn store(&self, message: &Message) -> Result<()> {
-        let mut messages = self.messages.write().await;
-        messages.push(message.clone());
-        debug!("Stored message: {}", message.id);
+    /// Replace older history with a provider-written summary if it has
+    /// grown past the compaction threshold
+    async fn compact_history(&self, session_id: &str) -> Result<()> {
+        self.usage.check_limit()?;
+        let summary = self
+            .compactor
+            .summarize(self.memory.as_ref(), self.provider.as_ref(), session_id)
+            .await?;
+
+        if let Some(summary) = summary {
+            let summary = self.record_usage(summary);
+            self.memory.compact(&summary).await?;
+            info!(
+                "Compacted {} messages into summary {}",
+                summary.summary_of().len(),
+                summary.id
+            );
+        }
         Ok(())
     }
 
-    async fn retrieve(&self, limit: usize) -> Result<Vec<Message>> {
-        let messages = self.messages.read().await;
-        let start = if messages.len() > limit {
-            messages.len() - limit
-        } else {
-            0
+    /// Query the provider, running requested tools and re-querying until the
+    /// model stops asking for tools or `max_tool_iterations` is reached.
+    ///
+    /// Calls still pending at the limit are answered with error results and
+    /// the model is asked once more, without tools, for a final answer, so
+    /// every stored tool call has a result. Every assistant and tool message
+    /// is stored in `input`'s session as it is produced.
+    async fn run_tool_loop(
+        &self,
+        mut conversation: Vec<Message>,
+        input: &Message,
+        settings: &TurnSettings,
+    ) -> Result<Message> {
+        let session_id = input.session();
+        let definitions: Vec<ToolDefinition> = self
+            .permitted_tools(settings)
+            .map(|tool| tool.definition())
+            .collect();
+        let max_iterations = settings.max_tool_iterations;
+        let options = GenerationOptions {
+            model: self.model.clone(),
+            ..GenerationOptions::default()
+        }
+        .with_defaults(&settings.generation);
+
+        for iteration in 1..=max_iterations {
+            let request = CompletionRequest::new(conversation.clone())
+                .with_tools(definitions.clone())
+                .with_options(options.clone());
+            let response = self.query(&request, input).await?;
+            conversation.push(response.clone());
+
+            let calls = response.tool_calls();
+            if calls.is_empty() {
+                return Ok(response);
+            }
+
+            if iteration == max_iterations {
+                warn!(
+                    "Tool iteration limit ({}) reached with {} pending tool calls",
+                    max_iterations,
+                    calls.len()
+                );
+                for call in calls {
+                    let result = tool_error(
+                        &call,
+                        serde_json::json!({
+                            "error": "iteration_limit",
+                            "message": format!(
+                                "Tool iteration limit ({}) reached; the tool was not run",
+                                max_iterations
+                            ),
+                        }),
+                    )
+                    .with_session(session_id);
+                    self.memory.store(&result).await?;
+                    conversation.push(result);
+                }
+                break;
+            }
+
+            debug!(
+                "Iteration {}: executing {} tool calls",
+                iteration,
+                calls.len()
+            );
+            for call in calls {
+                let result = self
+                    .execute_tool(&call, settings)
+                    .await
+                    .with_session(session_id);
+                self.memory.store(&result).await?;
+                conversation.push(result);
+            }
+        }
+
+        // Out of tool iterations: ask for an answer with no tools to call
+        let request = CompletionRequest::new(conversation).with_options(options);
+        self.query(&request, input).await
+    }
+
+    /// Send `request`, recording the response's usage and storing it in
+    /// `input`'s session
+    async fn query(&self, request: &CompletionRequest, input: &Message) -> Result<Message> {
+        self.usage.check_limit()?;
+        let response = self.complete(request, input).await?;
+        let response = self.record_usage(response.with_session(input.session()));
+        self.memory.store(&response).await?;
+        Ok(response)
+    }
+
+    /// Complete `request`, streaming the text to the output sink when it
+    /// asks for deltas
+    async fn complete(&self, request: &CompletionRequest, input: &Message) -> Result<Message> {
+        if !self.output.streams() {
+            return self.provider.complete(request).await;
+        }
+
+        let mut stream = self.provider.generate_stream(request).await?;
+        let mut accumulator = StreamAccumulator::new();
+        while let Some(event) = stream.next().await {
+            let event = event?;
+            if let StreamEvent::TextDelta(text) = &event {
+                if let Err(e) = self.output.delta(input, text).await {
+                    warn!("Failed to deliver reply chunk: {}", e);
+                }
+            }
+            accumulator.push(event);
+        }
+        // Priced as the model the stream's `Done` event names, which under a
+        // fallback chain need not be the configured one; that is only used
+        // for providers that name none
+        accumulator.into_message(&self.model())
+    }
+
+    /// Account for the tokens `response` used, recording its cost in
+    /// `metadata["cost_usd"]`
+    fn record_usage(&self, response: Message) -> Message {
+        let Some(usage) = response.usage() else {
+            return response;
         };
-        Ok(messages[start..].to_vec())
+
+        let provider = response
+            .metadata
+            .get(PROVIDER_METADATA_KEY)
+            .and_then(|name| name.as_str())
+            .unwrap_or_else(|| self.provider.name())
+            .to_string();
+        let model = response
+            .model()
+            .map(str::to_string)
+            .unwrap_or_else(|| self.model());
+
+        let cost = self
+            .usage
+            .record(response.session(), &provider, &model, &usage);
+        debug!(
+            "{} used {} tokens (${:.6})",
+            model,
+            usage.total_tokens(),
+            cost
+        );
+        response.with_metadata("cost_usd".to_string(), cost.into())
     }
 
-    async fn search(&self, query: &str) -> Result<Vec<Message>> {
-        let messages = self.messages.read().await;
-        let results: Vec<Message> = messages
-            .iter()
-            .filter(|msg| msg.content.to_lowercase().contains(&query.to_lowercase()))
-            .cloned()
+    /// Execute a single tool call, turning failures into an error result.
+    ///
+    /// Unknown tools, tools the `[tools]` configuration does not permit and
+    /// arguments that fail schema validation are reported back to the model
+    /// as structured JSON errors without running the tool.
+    #[instrument(name = "tool", skip_all, fields(tool = %call.name, call_id = %call.id))]
+    async fn execute_tool(&self, call: &ToolCall, settings: &TurnSettings) -> Message {
+        let available: Vec<&str> = self
+            .permitted_tools(settings)
+            .map(|tool| tool.name())
             .collect();
-        Ok(results)
+        let Some(tool) = self.tools.iter().find(|tool| tool.name() == call.name) else {
+            warn!("Model requested unknown tool: {}", call.name);
+            return tool_error(
+                call,
+                serde_json::json!({
+                    "error": "unknown_tool",
+                    "message": format!("Unknown tool: {}", call.name),
+                    "available_tools": available,
+                }),
+            );
+        };
+        if !settings.tools.permits(tool.name()) {
+            warn!("Model requested tool {}, which is not permitted", call.name);
+            return tool_error(
+                call,
+                serde_json::json!({
+                    "error": "tool_not_permitted",
+                    "message": format!("Tool {} is not permitted", call.name),
+                    "available_tools": available,
+                }),
+            );
+        }
+
+        let violations = schema::validate(&tool.input_schema(), &call.arguments);
+        if !violations.is_empty() {
+            warn!(
+                "Rejected call to {} with invalid arguments: {} problem(s)",
+                call.name,
+                violations.len()
+            );
+            let details: Vec<serde_json::Value> = violations
+                .iter()
+                .map(|v| serde_json::json!({ "path": v.path, "message": v.message }))
+                .collect();
+            return tool_error(
+                call,
+                serde_json::json!({
+                    "error": "invalid_arguments",
+                    "message": format!("Arguments for {} do not match its input schema", call.name),
+                    "details": details,
+                }),
+            );
+        }
+
+        debug!("Executing tool {} ({})", call.name, call.id);
+        match tool.execute(call.arguments.clone()).await {
+            Ok(output) => Message::tool_result(call.id.clone(), output, false),
+            Err(e) => {
+                warn!("Tool {} failed: {}", call.name, e);
+                tool_error(
+                    call,
+                    serde_json::json!({
+                        "error": "execution_failed",
+                        "message": e.to_string(),
+                    }),
+                )
+            }
+        }
     }
-}
\ No newline at end of file
+
+    /// Gracefully stop the agent
+    pub async fn stop(&self) {
+        let mut running = self.running.write().await;
+        *running = false;
+        // Wake the loop if it is waiting for input
+        let _ = self.inbox_sender.try_send(Input::Stop);
+        info!("SKYNET agent stop requested");
+    }
+}
+
+/// Next configuration published on `updates`; never resolves without a
+/// receiver or once the publisher is gone
+async fn next_config(updates: &mut Option<watch::Receiver<Arc<Config>>>) -> Option<Arc<Config>> {
+    let Some(receiver) = updates else {
+        return std::future::pending().await;
+    };
+    if receiver.changed().await.is_err() {
+        *updates = None;
+        return std::future::pending().await;
+    }
+    Some(Arc::clone(&receiver.borrow_and_update()))
+}
+
+/// Errors that will recur on every cycle until the operator intervenes
+fn is_fatal(error: &SkynetError) -> bool {
+    matches!(
+        error,
+        SkynetError::Config { .. }
+            | SkynetError::SpendLimitExceeded { .. }
+            | SkynetError::Provider(ProviderError::Authentication { .. })
+    )
+}
+
+/// Build an error tool result carrying a structured JSON payload
+fn tool_error(call: &ToolCall, payload: serde_json::Value) -> Message {
+    Message::tool_result(call.id.clone(), payload.to_string(), true)
+}
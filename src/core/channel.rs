@@ -0,0 +1,111 @@
+use crate::core::message::Message;
+use crate::{Result, SkynetError};
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use tracing::info;
+
+/// Messages that can wait in the agent's input queue before `send` blocks
+pub const INPUT_QUEUE_CAPACITY: usize = 64;
+
+/// An entry in the agent's input queue
+#[derive(Debug)]
+pub(crate) enum Input {
+    Message(Message),
+    Stop,
+}
+
+/// Cloneable handle for feeding messages to a running [`SkynetAgent`].
+///
+/// Messages of one session are processed one at a time in the order they are
+/// sent, while different sessions are processed concurrently; replies go to
+/// the agent's [`OutputSink`].
+///
+/// [`SkynetAgent`]: crate::SkynetAgent
+#[derive(Debug, Clone)]
+pub struct AgentHandle {
+    sender: mpsc::Sender<Input>,
+}
+
+impl AgentHandle {
+    pub(crate) fn new(sender: mpsc::Sender<Input>) -> Self {
+        Self { sender }
+    }
+
+    /// Queue `message` for the agent, waiting while the queue is full
+    pub async fn send(&self, message: Message) -> Result<()> {
+        self.sender
+            .send(Input::Message(message))
+            .await
+            .map_err(|_| SkynetError::agent("Agent is no longer accepting messages"))
+    }
+
+    /// Ask the agent loop to exit once the messages queued so far are handled
+    pub async fn stop(&self) -> Result<()> {
+        self.sender
+            .send(Input::Stop)
+            .await
+            .map_err(|_| SkynetError::agent("Agent is no longer accepting messages"))
+    }
+
+    /// Whether the agent has been dropped
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+/// Destination for the agent's replies.
+///
+/// Every reply carries the ID of the message it answers in
+/// `metadata["in_reply_to"]`; failed turns are delivered as
+/// [`MessageType::Error`](crate::MessageType::Error) messages.
+#[async_trait]
+pub trait OutputSink: Send + Sync {
+    async fn deliver(&self, reply: &Message) -> Result<()>;
+
+    /// Whether the agent should stream replies to [`delta`](Self::delta)
+    /// as they are generated
+    fn streams(&self) -> bool {
+        false
+    }
+
+    /// A chunk of the reply to `input`, sent before the finished reply is
+    /// delivered. Only called when [`streams`](Self::streams) is true.
+    async fn delta(&self, _input: &Message, _text: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Sink that logs replies at info level; the agent's default
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LogSink;
+
+#[async_trait]
+impl OutputSink for LogSink {
+    async fn deliver(&self, reply: &Message) -> Result<()> {
+        info!("Agent response: {}", reply.content);
+        Ok(())
+    }
+}
+
+/// Sink that forwards replies to an unbounded channel
+#[derive(Debug, Clone)]
+pub struct ChannelSink {
+    sender: mpsc::UnboundedSender<Message>,
+}
+
+impl ChannelSink {
+    /// Create a sink and the receiver its replies arrive on
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<Message>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+}
+
+#[async_trait]
+impl OutputSink for ChannelSink {
+    async fn deliver(&self, reply: &Message) -> Result<()> {
+        self.sender
+            .send(reply.clone())
+            .map_err(|_| SkynetError::agent("Reply receiver was dropped"))
+    }
+}
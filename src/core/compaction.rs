@@ -0,0 +1,152 @@
+use crate::core::agent::Memory;
+use crate::core::message::{Message, MessageType, Role};
+use crate::providers::{CompletionRequest, LLMProvider};
+use crate::{Result, SkynetError};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+/// Instructions given to the provider when summarizing history
+const SUMMARY_PROMPT: &str = "You compact conversation history for an AI agent. \
+Summarize the transcript you are given so the agent can continue the conversation \
+without it: keep facts about the user, decisions made, open tasks, tool results \
+that still matter, and any earlier summary's content. Be concise and write in the \
+third person. Reply with the summary only.";
+
+/// History compaction settings (`[agent.compaction]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompactionConfig {
+    /// Whether to compact history automatically
+    pub enabled: bool,
+
+    /// Compact once history holds more than this many messages
+    pub threshold_messages: usize,
+
+    /// Most recent messages left untouched by compaction
+    pub keep_recent: usize,
+}
+
+impl Default for CompactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold_messages: 200,
+            keep_recent: 50,
+        }
+    }
+}
+
+/// Summarizes older history into a single summary message.
+///
+/// System messages other than earlier summaries are never compacted, and an
+/// earlier summary is folded into the new one, so history holds at most one.
+#[derive(Debug, Clone)]
+pub struct Compactor {
+    config: CompactionConfig,
+}
+
+impl Compactor {
+    /// Create a compactor with the given settings
+    pub fn new(config: CompactionConfig) -> Self {
+        Self { config }
+    }
+
+    /// If the history of `session_id` exceeds the threshold, ask `provider`
+    /// to summarize the older turns and return the summary, ready for
+    /// [`Memory::compact`].
+    ///
+    /// The summary's timestamp is that of the newest message it replaces, so
+    /// it sorts in their place, and it carries the provider's usage metadata.
+    pub async fn summarize(
+        &self,
+        memory: &dyn Memory,
+        provider: &dyn LLMProvider,
+        session_id: &str,
+    ) -> Result<Option<Message>> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+        let threshold = self.config.threshold_messages;
+        if memory.retrieve(session_id, threshold + 1).await?.len() <= threshold {
+            return Ok(None);
+        }
+
+        let history = memory.retrieve(session_id, usize::MAX).await?;
+        let Some(older) = self.select(&history) else {
+            return Ok(None);
+        };
+
+        info!(
+            "Compacting {} of {} messages into a summary",
+            older.len(),
+            history.len()
+        );
+        let request = CompletionRequest::new(vec![
+            Message::system(SUMMARY_PROMPT.to_string()),
+            Message::user(transcript(&older)),
+        ]);
+        let response = provider.complete(&request).await?;
+
+        let content = response.content.trim();
+        if content.is_empty() {
+            return Err(SkynetError::agent("Provider returned an empty summary"));
+        }
+
+        let replaced = older.iter().map(|message| message.id.clone()).collect();
+        let mut summary = Message::summary(content.to_string(), replaced).with_session(session_id);
+        summary.timestamp = older[older.len() - 1].timestamp;
+        if let Some(usage) = response.usage() {
+            summary = summary.with_usage(response.model().unwrap_or(provider.model()), &usage);
+        }
+        debug!("Summary {}: {} chars", summary.id, summary.content.len());
+        Ok(Some(summary))
+    }
+
+    /// Older messages to compact: all but the last `keep_recent` compactable
+    /// messages, extended so the kept part starts on a user turn and no tool
+    /// call is separated from its result
+    fn select<'a>(&self, history: &'a [Message]) -> Option<Vec<&'a Message>> {
+        let compactable: Vec<&Message> = history
+            .iter()
+            .filter(|message| {
+                message.role != Role::System || message.message_type == MessageType::Summary
+            })
+            .collect();
+
+        let mut cut = compactable.len().saturating_sub(self.config.keep_recent);
+        while cut < compactable.len() && compactable[cut].role != Role::User {
+            cut += 1;
+        }
+
+        // Folding a lone earlier summary into a new one gains nothing
+        if cut < 2 {
+            return None;
+        }
+        Some(compactable[..cut].to_vec())
+    }
+}
+
+/// Render messages as a plain-text transcript for summarization
+fn transcript(messages: &[&Message]) -> String {
+    messages
+        .iter()
+        .map(|message| {
+            let speaker = match (&message.role, &message.message_type) {
+                (_, MessageType::Summary) => "Earlier summary",
+                (Role::System, _) => "System",
+                (Role::User, _) => "User",
+                (Role::Assistant, _) => "Assistant",
+                (Role::Tool, _) => "Tool result",
+            };
+            let mut line = format!("{}: {}", speaker, message.content);
+            for call in message.tool_calls() {
+                line.push_str(&format!(
+                    "\n  [called {} with {}]",
+                    call.name, call.arguments
+                ));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
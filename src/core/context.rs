@@ -0,0 +1,344 @@
+use crate::core::message::{Message, Role};
+use crate::memory::search::SearchHit;
+use std::collections::HashSet;
+use tracing::{debug, warn};
+
+/// Rough characters-per-token ratio used for estimates
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Per-message token overhead for role and formatting
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Estimate how many tokens `message` takes up in a request
+pub fn estimate_tokens(message: &Message) -> usize {
+    let mut chars = message.content.chars().count();
+    if let Some(calls) = message.metadata.get("tool_calls") {
+        chars += calls.to_string().len();
+    }
+    chars_to_tokens(chars) + MESSAGE_OVERHEAD_TOKENS
+}
+
+fn chars_to_tokens(chars: usize) -> usize {
+    chars.div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Conversation assembled for one request
+#[derive(Debug, Clone, Default)]
+pub struct ContextWindow {
+    /// Messages to send, in order
+    pub messages: Vec<Message>,
+
+    /// Older turns that did not fit, oldest first; they are left out of the
+    /// request, not summarized
+    pub dropped: Vec<Message>,
+
+    /// Estimated tokens of `messages`
+    pub estimated_tokens: usize,
+}
+
+/// Assembles a conversation that fits a token budget.
+///
+/// System messages and the new input are always included. The rest of the
+/// budget goes to the most recent turns, newest first, then to a digest of
+/// relevant older messages found by search. Turns that do not fit are
+/// returned in [`ContextWindow::dropped`] but not summarized here: only
+/// compaction ([`Compactor`]) writes summaries, and until it runs dropped
+/// turns can still be recalled by search.
+///
+/// [`Compactor`]: crate::core::compaction::Compactor
+#[derive(Debug, Clone)]
+pub struct ContextBuilder {
+    budget_tokens: usize,
+    max_messages: usize,
+    max_recalled: usize,
+}
+
+impl ContextBuilder {
+    /// Create a builder for a budget of `budget_tokens`
+    pub fn new(budget_tokens: usize) -> Self {
+        Self {
+            budget_tokens,
+            max_messages: usize::MAX,
+            max_recalled: usize::MAX,
+        }
+    }
+
+    /// Include at most this many recent turns
+    pub fn with_max_messages(mut self, max_messages: usize) -> Self {
+        self.max_messages = max_messages;
+        self
+    }
+
+    /// Include at most this many search hits
+    pub fn with_max_recalled(mut self, max_recalled: usize) -> Self {
+        self.max_recalled = max_recalled;
+        self
+    }
+
+    /// Build the context for `input` from the stored `history` (oldest first),
+    /// pinned `system` messages and search `hits` ranked by relevance
+    pub fn build(
+        &self,
+        system: &[Message],
+        history: &[Message],
+        hits: &[SearchHit],
+        input: &Message,
+    ) -> ContextWindow {
+        let mut used: usize =
+            system.iter().map(estimate_tokens).sum::<usize>() + estimate_tokens(input);
+        if used > self.budget_tokens {
+            warn!(
+                "Pinned messages need ~{} tokens, over the {} token budget",
+                used, self.budget_tokens
+            );
+        }
+
+        let turns: Vec<&Message> = history
+            .iter()
+            .filter(|message| message.role != Role::System && message.id != input.id)
+            .collect();
+
+        // Newest turns first, stopping at the first one that does not fit
+        let mut kept = 0;
+        for message in turns.iter().rev() {
+            let cost = estimate_tokens(message);
+            if kept == self.max_messages || used + cost > self.budget_tokens {
+                break;
+            }
+            used += cost;
+            kept += 1;
+        }
+
+        let mut start = turns.len() - kept;
+        // Never open on a tool result whose call was cut off, or on an
+        // assistant turn; providers expect the conversation to start with
+        // the user
+        while start < turns.len() && turns[start].role != Role::User {
+            used -= estimate_tokens(turns[start]);
+            start += 1;
+        }
+        let recent = &turns[start..];
+        let dropped: Vec<Message> = turns[..start].iter().map(|m| (*m).clone()).collect();
+
+        let included: HashSet<&str> = recent
+            .iter()
+            .map(|message| message.id.as_str())
+            .chain(system.iter().map(|message| message.id.as_str()))
+            .chain(std::iter::once(input.id.as_str()))
+            .collect();
+        let recalled = self.recall(hits, &included, &mut used);
+
+        let mut messages = system.to_vec();
+        messages.extend(recalled);
+        messages.extend(recent.iter().map(|message| (*message).clone()));
+        messages.push(input.clone());
+
+        debug!(
+            "Context: {} messages, ~{} tokens, {} dropped",
+            messages.len(),
+            used,
+            dropped.len()
+        );
+        ContextWindow {
+            messages,
+            dropped,
+            estimated_tokens: used,
+        }
+    }
+
+    /// Digest of the relevant search hits that fit the remaining budget, as a
+    /// single system message
+    fn recall(
+        &self,
+        hits: &[SearchHit],
+        included: &HashSet<&str>,
+        used: &mut usize,
+    ) -> Option<Message> {
+        let header = "Relevant earlier messages:";
+        let mut lines = Vec::new();
+        let mut cost = chars_to_tokens(header.len()) + MESSAGE_OVERHEAD_TOKENS;
+
+        for hit in hits {
+            if lines.len() == self.max_recalled {
+                break;
+            }
+            if included.contains(hit.message.id.as_str()) {
+                continue;
+            }
+            let line = format!(
+                "- [{}] {:?}: {}",
+                hit.message.timestamp.format("%Y-%m-%d %H:%M"),
+                hit.message.role,
+                hit.message.content
+            );
+            let line_cost = chars_to_tokens(line.chars().count());
+            if *used + cost + line_cost > self.budget_tokens {
+                continue;
+            }
+            cost += line_cost;
+            lines.push(line);
+        }
+
+        if lines.is_empty() {
+            return None;
+        }
+        *used += cost;
+        Some(Message::system(format!("{}\n{}", header, lines.join("\n"))))
+    }
+}
+
+/// Common words that make poor search terms
+const STOPWORDS: &[&str] = &[
+    "about", "and", "are", "but", "can", "could", "did", "does", "for", "from", "had", "has",
+    "have", "how", "its", "not", "our", "out", "should", "that", "the", "their", "them", "then",
+    "there", "these", "they", "this", "was", "were", "what", "when", "where", "which", "who",
+    "why", "will", "with", "would", "you", "your",
+];
+
+/// Distinctive words of `text` to search memory with
+pub fn keywords(text: &str) -> String {
+    let mut seen = HashSet::new();
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .filter(|word| seen.insert(word.clone()))
+        .take(12)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A message of 13 estimated tokens: 36 characters plus the overhead
+    fn turn(role: Role, label: &str) -> Message {
+        let content = format!("{:-<36}", label);
+        match role {
+            Role::System => Message::system(content),
+            Role::User => Message::user(content),
+            _ => Message::assistant(content),
+        }
+    }
+
+    /// Alternating user and assistant turns labelled u1, a1, u2, a2, ...
+    fn history(pairs: usize) -> Vec<Message> {
+        (1..=pairs)
+            .flat_map(|n| {
+                [
+                    turn(Role::User, &format!("u{}", n)),
+                    turn(Role::Assistant, &format!("a{}", n)),
+                ]
+            })
+            .collect()
+    }
+
+    fn labels(messages: &[Message]) -> Vec<&str> {
+        messages
+            .iter()
+            .map(|message| message.content.trim_end_matches('-'))
+            .collect()
+    }
+
+    fn hit(message: &Message) -> SearchHit {
+        SearchHit {
+            message: message.clone(),
+            score: 1.0,
+            snippet: String::new(),
+        }
+    }
+
+    #[test]
+    fn keeps_the_newest_turns_that_fit_the_budget() {
+        let system = [turn(Role::System, "sys")];
+        let history = history(3);
+        let input = turn(Role::User, "input");
+        assert_eq!(estimate_tokens(&input), 13);
+
+        // Room for three turns besides the pinned ones, but the oldest of
+        // those is an assistant turn, which the window may not open on
+        let window = ContextBuilder::new(13 * 5).build(&system, &history, &[], &input);
+        assert_eq!(labels(&window.messages), ["sys", "u3", "a3", "input"]);
+        assert_eq!(labels(&window.dropped), ["u1", "a1", "u2", "a2"]);
+        assert_eq!(window.estimated_tokens, 13 * 4);
+        assert!(window.estimated_tokens <= 13 * 5);
+
+        let window = ContextBuilder::new(usize::MAX).with_max_messages(2).build(
+            &system,
+            &history,
+            &[],
+            &input,
+        );
+        assert_eq!(labels(&window.messages), ["sys", "u3", "a3", "input"]);
+    }
+
+    #[test]
+    fn pins_system_messages_and_the_input_over_budget() {
+        let system = [turn(Role::System, "sys1"), turn(Role::System, "sys2")];
+        let mut history = history(2);
+        // Stored system messages and the input itself are not history turns
+        history.insert(1, turn(Role::System, "stored"));
+        let input = turn(Role::User, "input");
+        history.push(input.clone());
+
+        let window = ContextBuilder::new(10).build(&system, &history, &[], &input);
+        assert_eq!(labels(&window.messages), ["sys1", "sys2", "input"]);
+        assert_eq!(labels(&window.dropped), ["u1", "a1", "u2", "a2"]);
+        assert_eq!(window.estimated_tokens, 13 * 3);
+    }
+
+    #[test]
+    fn never_opens_on_a_tool_result_whose_call_was_cut_off() {
+        let call = Message::tool_call(
+            String::new(),
+            vec![crate::core::message::ToolCall {
+                id: "call-1".to_string(),
+                name: "weather".to_string(),
+                arguments: serde_json::json!({}),
+            }],
+        );
+        let result = Message::tool_result("call-1".to_string(), format!("{:-<36}", "r"), false);
+        let history = [
+            turn(Role::User, "u1"),
+            call,
+            result,
+            turn(Role::Assistant, "a1"),
+        ];
+        let input = turn(Role::User, "input");
+
+        let window = ContextBuilder::new(13 * 3).build(&[], &history, &[], &input);
+        assert_eq!(labels(&window.messages), ["input"]);
+        assert_eq!(window.dropped.len(), 4);
+    }
+
+    #[test]
+    fn recalls_relevant_dropped_turns_within_the_budget() {
+        let history = history(3);
+        let input = turn(Role::User, "input");
+        let hits = [hit(&history[5]), hit(&history[0]), hit(&history[2])];
+
+        let window = ContextBuilder::new(200)
+            .with_max_messages(2)
+            .with_max_recalled(1)
+            .build(&[], &history, &hits, &input);
+        // a3 is already in the window, so the best other hit is recalled
+        assert_eq!(window.messages.len(), 4);
+        let recalled = &window.messages[0];
+        assert_eq!(recalled.role, Role::System);
+        assert!(recalled.content.starts_with("Relevant earlier messages:"));
+        assert!(recalled.content.contains("User: u1"));
+        assert!(!recalled.content.contains("u2"));
+        assert_eq!(labels(&window.messages[1..]), ["u3", "a3", "input"]);
+        assert!(window.estimated_tokens <= 200);
+
+        // Nothing is recalled when no hit fits the remaining budget
+        let window = ContextBuilder::new(13 * 3 + 5).with_max_messages(2).build(
+            &[],
+            &history,
+            &hits,
+            &input,
+        );
+        assert_eq!(labels(&window.messages), ["u3", "a3", "input"]);
+    }
+}
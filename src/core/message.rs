@@ -1,3 +1,5 @@
+use crate::core::session::DEFAULT_SESSION_ID;
+use crate::providers::Usage;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -11,12 +13,21 @@ pub enum Role {
 }
 
 /// Message type classification
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum MessageType {
     Text,
     ToolCall,
     ToolResult,
     Error,
+    Summary,
+}
+
+/// A tool invocation requested by the model
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
 }
 
 /// Core message structure
@@ -28,6 +39,9 @@ pub struct Message {
     pub message_type: MessageType,
     pub metadata: HashMap<String, serde_json::Value>,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Session the message belongs to; `None` means the default session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
 }
 
 impl Message {
@@ -40,6 +54,7 @@ impl Message {
             message_type: MessageType::Text,
             metadata: HashMap::new(),
             timestamp: chrono::Utc::now(),
+            session_id: None,
         }
     }
 
@@ -58,9 +73,114 @@ impl Message {
         Self::new_text(Role::Assistant, content)
     }
 
+    /// Assign the message to the session with `id`
+    pub fn with_session(mut self, id: &str) -> Self {
+        self.session_id = Some(id.to_string());
+        self
+    }
+
+    /// ID of the session the message belongs to
+    pub fn session(&self) -> &str {
+        self.session_id.as_deref().unwrap_or(DEFAULT_SESSION_ID)
+    }
+
+    /// Create an assistant message reporting a failure
+    pub fn error(content: String) -> Self {
+        let mut message = Self::new_text(Role::Assistant, content);
+        message.message_type = MessageType::Error;
+        message
+    }
+
     /// Add metadata to the message
     pub fn with_metadata(mut self, key: String, value: serde_json::Value) -> Self {
         self.metadata.insert(key, value);
         self
     }
-}
\ No newline at end of file
+
+    /// Create an assistant message that requests tool calls
+    pub fn tool_call(content: String, calls: Vec<ToolCall>) -> Self {
+        let mut message = Self::new_text(Role::Assistant, content);
+        message.message_type = MessageType::ToolCall;
+        message.with_metadata(
+            "tool_calls".to_string(),
+            serde_json::to_value(calls).unwrap_or_default(),
+        )
+    }
+
+    /// Create a system message summarizing the messages with `replaced` IDs
+    pub fn summary(content: String, replaced: Vec<String>) -> Self {
+        let mut message = Self::new_text(Role::System, content);
+        message.message_type = MessageType::Summary;
+        message.with_metadata("summary_of".to_string(), replaced.into())
+    }
+
+    /// Create a tool result message answering the call with `tool_call_id`
+    pub fn tool_result(tool_call_id: String, content: String, is_error: bool) -> Self {
+        let mut message = Self::new_text(Role::Tool, content);
+        message.message_type = MessageType::ToolResult;
+        message
+            .with_metadata("tool_call_id".to_string(), tool_call_id.into())
+            .with_metadata("is_error".to_string(), is_error.into())
+    }
+
+    /// Mark this message as the reply to the message with `id`
+    pub fn in_reply_to(self, id: &str) -> Self {
+        self.with_metadata("in_reply_to".to_string(), id.into())
+    }
+
+    /// ID of the message this one replies to, if any
+    pub fn reply_to(&self) -> Option<&str> {
+        self.metadata.get("in_reply_to").and_then(|id| id.as_str())
+    }
+
+    /// Tool calls requested by this message, if any
+    pub fn tool_calls(&self) -> Vec<ToolCall> {
+        self.metadata
+            .get("tool_calls")
+            .and_then(|calls| serde_json::from_value(calls.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// ID of the tool call this message answers, if it is a tool result
+    pub fn tool_call_id(&self) -> Option<&str> {
+        self.metadata.get("tool_call_id").and_then(|id| id.as_str())
+    }
+
+    /// IDs of the messages this summary replaced, if it is a summary
+    pub fn summary_of(&self) -> Vec<String> {
+        self.metadata
+            .get("summary_of")
+            .and_then(|ids| serde_json::from_value(ids.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Record the token usage and model of the completion that produced
+    /// this message
+    pub fn with_usage(self, model: &str, usage: &Usage) -> Self {
+        self.with_metadata(
+            "usage".to_string(),
+            serde_json::to_value(usage).unwrap_or_default(),
+        )
+        .with_metadata("model".to_string(), model.into())
+    }
+
+    /// Token usage of the completion that produced this message, if known
+    pub fn usage(&self) -> Option<Usage> {
+        self.metadata
+            .get("usage")
+            .and_then(|usage| serde_json::from_value(usage.clone()).ok())
+    }
+
+    /// Model that produced this message, if known
+    pub fn model(&self) -> Option<&str> {
+        self.metadata.get("model").and_then(|model| model.as_str())
+    }
+
+    /// Whether this tool result reports a failure
+    pub fn is_error(&self) -> bool {
+        self.metadata
+            .get("is_error")
+            .and_then(|flag| flag.as_bool())
+            .unwrap_or(false)
+    }
+}
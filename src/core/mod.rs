@@ -1,7 +1,14 @@
 //! Core SKYNET components
 
 pub mod agent;
+pub mod channel;
+pub mod compaction;
+pub mod context;
 pub mod message;
+pub mod schema;
+pub mod session;
+pub mod usage;
 
 pub use agent::SkynetAgent;
-pub use message::{Message, MessageType, Role};
\ No newline at end of file
+pub use channel::{AgentHandle, ChannelSink, LogSink, OutputSink};
+pub use message::{Message, MessageType, Role, ToolCall};
@@ -0,0 +1,418 @@
+//! Minimal JSON Schema validation for tool arguments
+//!
+//! Supports the subset of JSON Schema that tool declarations use in
+//! practice: `type`, `properties`, `required`, `additionalProperties`,
+//! `items`, `enum`, `const`, numeric and length bounds, and the `allOf` /
+//! `anyOf` / `oneOf` combinators. Unknown keywords are ignored.
+
+use serde_json::{Map, Value};
+
+/// A single validation failure
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    /// JSON pointer to the offending value (empty for the root)
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Validate `value` against `schema`, returning every failure found
+pub fn validate(schema: &Value, value: &Value) -> Vec<SchemaError> {
+    let mut errors = Vec::new();
+    validate_at(schema, value, "", &mut errors);
+    errors
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str, errors: &mut Vec<SchemaError>) {
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            errors.push(error(path, "no value is allowed here"));
+            return;
+        }
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+
+    if let Some(expected) = schema.get("type") {
+        if !matches_type(expected, value) {
+            errors.push(error(
+                path,
+                format!(
+                    "expected {}, found {}",
+                    describe_type(expected),
+                    type_name(value)
+                ),
+            ));
+            // Further keywords would only produce noise for the wrong type
+            return;
+        }
+    }
+
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            errors.push(error(
+                path,
+                format!("must be one of {}", Value::Array(options.clone())),
+            ));
+        }
+    }
+
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(error(path, format!("must equal {}", expected)));
+        }
+    }
+
+    match value {
+        Value::Object(object) => validate_object(schema, object, path, errors),
+        Value::Array(items) => validate_array(schema, items, path, errors),
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if length < min {
+                    errors.push(error(path, format!("must be at least {} characters", min)));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if length > max {
+                    errors.push(error(path, format!("must be at most {} characters", max)));
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            validate_number(schema, number, path, errors);
+        }
+        _ => {}
+    }
+
+    validate_combinators(schema, value, path, errors);
+}
+
+fn validate_object(
+    schema: &Map<String, Value>,
+    object: &Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(name) {
+                errors.push(error(path, format!("missing required property '{}'", name)));
+            }
+        }
+    }
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (name, property) in object {
+        let property_path = format!("{}/{}", path, name);
+        match properties.and_then(|properties| properties.get(name)) {
+            Some(property_schema) => validate_at(property_schema, property, &property_path, errors),
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    errors.push(error(path, format!("unexpected property '{}'", name)));
+                }
+                Some(additional) => validate_at(additional, property, &property_path, errors),
+                None => {}
+            },
+        }
+    }
+}
+
+fn validate_array(
+    schema: &Map<String, Value>,
+    items: &[Value],
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+        if (items.len() as u64) < min {
+            errors.push(error(path, format!("must contain at least {} items", min)));
+        }
+    }
+    if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+        if (items.len() as u64) > max {
+            errors.push(error(path, format!("must contain at most {} items", max)));
+        }
+    }
+    if let Some(item_schema) = schema.get("items") {
+        for (index, item) in items.iter().enumerate() {
+            validate_at(item_schema, item, &format!("{}/{}", path, index), errors);
+        }
+    }
+}
+
+fn validate_number(
+    schema: &Map<String, Value>,
+    number: f64,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+        if number < min {
+            errors.push(error(path, format!("must be >= {}", min)));
+        }
+    }
+    if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+        if number > max {
+            errors.push(error(path, format!("must be <= {}", max)));
+        }
+    }
+    if let Some(min) = schema.get("exclusiveMinimum").and_then(Value::as_f64) {
+        if number <= min {
+            errors.push(error(path, format!("must be > {}", min)));
+        }
+    }
+    if let Some(max) = schema.get("exclusiveMaximum").and_then(Value::as_f64) {
+        if number >= max {
+            errors.push(error(path, format!("must be < {}", max)));
+        }
+    }
+}
+
+fn validate_combinators(
+    schema: &Map<String, Value>,
+    value: &Value,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    if let Some(Value::Array(all)) = schema.get("allOf") {
+        for sub in all {
+            validate_at(sub, value, path, errors);
+        }
+    }
+
+    let matching = |options: &[Value]| {
+        options
+            .iter()
+            .filter(|sub| validate(sub, value).is_empty())
+            .count()
+    };
+
+    if let Some(Value::Array(any)) = schema.get("anyOf") {
+        if matching(any) == 0 {
+            errors.push(error(path, "does not match any of the allowed schemas"));
+        }
+    }
+    if let Some(Value::Array(one)) = schema.get("oneOf") {
+        let count = matching(one);
+        if count != 1 {
+            errors.push(error(
+                path,
+                format!("must match exactly one allowed schema, matched {}", count),
+            ));
+        }
+    }
+}
+
+fn matches_type(expected: &Value, value: &Value) -> bool {
+    match expected {
+        Value::String(name) => is_type(name, value),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|name| is_type(name, value)),
+        _ => true,
+    }
+}
+
+fn is_type(name: &str, value: &Value) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => match value {
+            Value::Number(n) => {
+                n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+            }
+            _ => false,
+        },
+        _ => true,
+    }
+}
+
+fn describe_type(expected: &Value) -> String {
+    match expected {
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        other => other.as_str().unwrap_or("valid type").to_string(),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn error(path: &str, message: impl Into<String>) -> SchemaError {
+    SchemaError {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paths(errors: &[SchemaError]) -> Vec<&str> {
+        errors.iter().map(|e| e.path.as_str()).collect()
+    }
+
+    #[test]
+    fn accepts_matching_values() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string", "minLength": 1 },
+                "limit": { "type": "integer", "minimum": 1, "maximum": 50 }
+            },
+            "required": ["query"]
+        });
+        assert!(validate(&schema, &json!({ "query": "rust", "limit": 10 })).is_empty());
+    }
+
+    #[test]
+    fn reports_type_mismatches() {
+        let errors = validate(&json!({ "type": "string" }), &json!(42));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "expected string, found number");
+
+        let errors = validate(&json!({ "type": ["string", "null"] }), &json!(true));
+        assert_eq!(errors[0].message, "expected string or null, found boolean");
+        assert!(validate(&json!({ "type": ["string", "null"] }), &json!(null)).is_empty());
+    }
+
+    #[test]
+    fn reports_every_missing_required_property() {
+        let schema = json!({ "type": "object", "required": ["a", "b", "c"] });
+        let errors = validate(&schema, &json!({ "b": 1 }));
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "missing required property 'a'",
+                "missing required property 'c'"
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_additional_properties_when_disallowed() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "known": { "type": "string" } },
+            "additionalProperties": false
+        });
+        let errors = validate(&schema, &json!({ "known": "x", "extra": 1 }));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "unexpected property 'extra'");
+
+        // A schema for additional properties validates each of them
+        let schema = json!({ "type": "object", "additionalProperties": { "type": "number" } });
+        let errors = validate(&schema, &json!({ "a": 1, "b": "two" }));
+        assert_eq!(paths(&errors), ["/b"]);
+
+        // Without the keyword anything goes
+        assert!(validate(&json!({ "type": "object" }), &json!({ "extra": 1 })).is_empty());
+    }
+
+    #[test]
+    fn reports_nested_paths() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "filters": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "field": { "type": "string" } },
+                        "required": ["field"]
+                    }
+                }
+            }
+        });
+        let value = json!({ "filters": [{ "field": "a" }, { "field": 3 }, {}] });
+        let errors = validate(&schema, &value);
+        assert_eq!(paths(&errors), ["/filters/1/field", "/filters/2"]);
+        assert_eq!(
+            errors[0].to_string(),
+            "/filters/1/field: expected string, found number"
+        );
+    }
+
+    #[test]
+    fn any_of_needs_one_match_and_one_of_exactly_one() {
+        let any = json!({ "anyOf": [{ "type": "string" }, { "type": "integer" }] });
+        assert!(validate(&any, &json!("x")).is_empty());
+        assert!(validate(&any, &json!(3)).is_empty());
+        let errors = validate(&any, &json!(1.5));
+        assert_eq!(
+            errors[0].message,
+            "does not match any of the allowed schemas"
+        );
+
+        let one = json!({ "oneOf": [{ "type": "number" }, { "type": "integer" }] });
+        assert!(validate(&one, &json!(1.5)).is_empty());
+        let errors = validate(&one, &json!(2));
+        assert_eq!(
+            errors[0].message,
+            "must match exactly one allowed schema, matched 2"
+        );
+        let errors = validate(&one, &json!("x"));
+        assert_eq!(
+            errors[0].message,
+            "must match exactly one allowed schema, matched 0"
+        );
+    }
+
+    #[test]
+    fn integers_include_whole_floats_but_not_fractions() {
+        let schema = json!({ "type": "integer" });
+        assert!(validate(&schema, &json!(7)).is_empty());
+        assert!(validate(&schema, &json!(-7)).is_empty());
+        assert!(validate(&schema, &json!(u64::MAX)).is_empty());
+        assert!(validate(&schema, &json!(3.0)).is_empty());
+        assert_eq!(validate(&schema, &json!(3.5)).len(), 1);
+        assert_eq!(validate(&schema, &json!("3")).len(), 1);
+    }
+
+    #[test]
+    fn checks_bounds_enums_and_constants() {
+        let schema = json!({ "type": "number", "exclusiveMinimum": 0, "maximum": 1 });
+        assert!(validate(&schema, &json!(0.5)).is_empty());
+        assert_eq!(validate(&schema, &json!(0))[0].message, "must be > 0");
+        assert_eq!(validate(&schema, &json!(2))[0].message, "must be <= 1");
+
+        let schema = json!({ "enum": ["asc", "desc"] });
+        assert!(validate(&schema, &json!("asc")).is_empty());
+        assert_eq!(validate(&schema, &json!("up")).len(), 1);
+
+        assert_eq!(validate(&json!({ "const": 1 }), &json!(2)).len(), 1);
+        assert_eq!(validate(&json!(false), &json!(null)).len(), 1);
+        assert!(validate(&json!(true), &json!(null)).is_empty());
+    }
+}
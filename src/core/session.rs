@@ -0,0 +1,174 @@
+use crate::core::agent::Memory;
+use crate::core::message::Message;
+use crate::{Result, SkynetError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+
+/// Session that messages without a session ID belong to
+pub const DEFAULT_SESSION_ID: &str = "default";
+
+/// A conversation with its own isolated history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub title: Option<String>,
+
+    /// Session this one was forked from
+    pub parent_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+
+    /// Time of the last message or change
+    pub updated_at: DateTime<Utc>,
+
+    /// Archived sessions keep their history but accept no new messages
+    pub archived: bool,
+}
+
+impl Session {
+    /// Create a session with a fresh ID
+    pub fn new(title: Option<String>) -> Self {
+        Self::with_id(uuid::Uuid::new_v4().to_string(), title)
+    }
+
+    /// Create a session with the given ID
+    pub fn with_id(id: impl Into<String>, title: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: id.into(),
+            title,
+            parent_id: None,
+            created_at: now,
+            updated_at: now,
+            archived: false,
+        }
+    }
+}
+
+/// Creates, lists, forks, archives and deletes sessions in a memory backend.
+///
+/// Cheap to clone, so it can be used while the agent loop is running.
+#[derive(Clone)]
+pub struct SessionManager {
+    memory: Arc<dyn Memory>,
+}
+
+impl SessionManager {
+    /// Manage the sessions stored in `memory`
+    pub fn new(memory: Arc<dyn Memory>) -> Self {
+        Self { memory }
+    }
+
+    /// Start a new, empty session
+    pub async fn create(&self, title: Option<String>) -> Result<Session> {
+        let session = Session::new(title);
+        self.memory.save_session(&session).await?;
+        info!("Created session {}", session.id);
+        Ok(session)
+    }
+
+    /// Sessions, most recently active first
+    pub async fn list(&self, include_archived: bool) -> Result<Vec<Session>> {
+        let mut sessions = self.memory.sessions().await?;
+        sessions.retain(|session| include_archived || !session.archived);
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.updated_at));
+        Ok(sessions)
+    }
+
+    /// Look up a session by ID
+    pub async fn get(&self, id: &str) -> Result<Option<Session>> {
+        self.memory.session(id).await
+    }
+
+    /// Current history of a session, oldest first; compacted messages
+    /// appear as their summary
+    pub async fn history(&self, id: &str) -> Result<Vec<Message>> {
+        self.require(id).await?;
+        self.memory.retrieve(id, usize::MAX).await
+    }
+
+    /// Start a new session whose history is a copy of `id`'s.
+    ///
+    /// The copy holds the source's current history, including any summary
+    /// in place of compacted messages; the two then evolve independently.
+    pub async fn fork(&self, id: &str, title: Option<String>) -> Result<Session> {
+        let source = self.require(id).await?;
+        let mut fork = Session::new(title.or_else(|| {
+            source
+                .title
+                .as_ref()
+                .map(|title| format!("{} (fork)", title))
+        }));
+        fork.parent_id = Some(source.id.clone());
+        self.memory.save_session(&fork).await?;
+
+        let history = self.memory.retrieve(&source.id, usize::MAX).await?;
+        for message in &history {
+            let mut copy = message.clone().with_session(&fork.id);
+            copy.id = uuid::Uuid::new_v4().to_string();
+            self.memory.store(&copy).await?;
+        }
+
+        info!(
+            "Forked session {} into {} ({} messages)",
+            source.id,
+            fork.id,
+            history.len()
+        );
+        Ok(fork)
+    }
+
+    /// Keep a session's history but stop it from accepting messages
+    pub async fn archive(&self, id: &str) -> Result<Session> {
+        if id == DEFAULT_SESSION_ID {
+            return Err(SkynetError::agent("The default session cannot be archived"));
+        }
+        let mut session = self.require(id).await?;
+        session.archived = true;
+        session.updated_at = Utc::now();
+        self.memory.save_session(&session).await?;
+        info!("Archived session {}", id);
+        Ok(session)
+    }
+
+    /// Delete a session and all of its messages
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        if id == DEFAULT_SESSION_ID {
+            return Err(SkynetError::agent("The default session cannot be deleted"));
+        }
+        self.require(id).await?;
+        self.memory.delete_session(id).await?;
+        info!("Deleted session {}", id);
+        Ok(())
+    }
+
+    /// The session `id`, failing if it does not exist
+    pub async fn require(&self, id: &str) -> Result<Session> {
+        self.get(id)
+            .await?
+            .ok_or_else(|| SkynetError::agent(format!("Unknown session: {}", id)))
+    }
+
+    /// Create the default session if the backend does not have it yet
+    pub(crate) async fn ensure_default(&self) -> Result<()> {
+        if self.get(DEFAULT_SESSION_ID).await?.is_none() {
+            self.memory
+                .save_session(&Session::with_id(DEFAULT_SESSION_ID, None))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// The session `id`, failing if it cannot take new messages; marks it
+    /// as active now
+    pub(crate) async fn open(&self, id: &str) -> Result<Session> {
+        let mut session = self.require(id).await?;
+        if session.archived {
+            return Err(SkynetError::agent(format!("Session {} is archived", id)));
+        }
+        session.updated_at = Utc::now();
+        self.memory.save_session(&session).await?;
+        Ok(session)
+    }
+}
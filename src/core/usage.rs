@@ -0,0 +1,272 @@
+use crate::providers::Usage;
+use crate::{Config, Result, SkynetError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Price of a model in USD per million tokens (`[pricing."<model>"]` tables)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelPricing {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+    pub cache_write_per_mtok: f64,
+    pub cache_read_per_mtok: f64,
+}
+
+impl ModelPricing {
+    /// Cost in USD of a request with the given usage
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.input_tokens as f64 * self.input_per_mtok
+            + usage.output_tokens as f64 * self.output_per_mtok
+            + usage.cache_creation_input_tokens as f64 * self.cache_write_per_mtok
+            + usage.cache_read_input_tokens as f64 * self.cache_read_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// Token usage and cost accumulated over a set of requests
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageSummary {
+    pub requests: u64,
+    pub usage: Usage,
+    pub cost_usd: f64,
+}
+
+impl UsageSummary {
+    fn add(&mut self, usage: &Usage, cost: f64) {
+        self.requests += 1;
+        self.usage += usage;
+        self.cost_usd += cost;
+    }
+}
+
+/// Usage totals overall, per session and per provider
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageReport {
+    pub total: UsageSummary,
+    pub by_session: BTreeMap<String, UsageSummary>,
+    pub by_provider: BTreeMap<String, UsageSummary>,
+}
+
+/// Aggregates token usage and cost, and enforces an optional spend limit.
+///
+/// Models are priced by exact name, falling back to the longest configured
+/// prefix, so `claude-3-5-sonnet` also covers its dated releases. Models
+/// without a price are counted at zero cost.
+pub struct UsageTracker {
+    prices: BTreeMap<String, ModelPricing>,
+    spend_limit_usd: Option<f64>,
+    report: Mutex<UsageReport>,
+    unpriced: Mutex<BTreeSet<String>>,
+}
+
+impl UsageTracker {
+    /// Create a tracker with the given price table and spend limit
+    pub fn new(prices: BTreeMap<String, ModelPricing>, spend_limit_usd: Option<f64>) -> Self {
+        Self {
+            prices,
+            spend_limit_usd,
+            report: Mutex::new(UsageReport::default()),
+            unpriced: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Tracker using the `[pricing]` table and `[agent] spend_limit_usd`
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.pricing.clone(), config.agent.spend_limit_usd)
+    }
+
+    /// Price of `model`, if the table covers it
+    pub fn pricing(&self, model: &str) -> Option<&ModelPricing> {
+        self.prices.get(model).or_else(|| {
+            self.prices
+                .iter()
+                .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, pricing)| pricing)
+        })
+    }
+
+    /// Record one request and return its cost in USD
+    pub fn record(&self, session: &str, provider: &str, model: &str, usage: &Usage) -> f64 {
+        let cost = match self.pricing(model) {
+            Some(pricing) => pricing.cost(usage),
+            None => {
+                if lock(&self.unpriced).insert(model.to_string()) {
+                    warn!(
+                        "No price configured for model {}; counting it as free",
+                        model
+                    );
+                }
+                0.0
+            }
+        };
+
+        let mut report = lock(&self.report);
+        report.total.add(usage, cost);
+        report
+            .by_session
+            .entry(session.to_string())
+            .or_default()
+            .add(usage, cost);
+        report
+            .by_provider
+            .entry(provider.to_string())
+            .or_default()
+            .add(usage, cost);
+        cost
+    }
+
+    /// Snapshot of the totals so far
+    pub fn report(&self) -> UsageReport {
+        lock(&self.report).clone()
+    }
+
+    /// Total spend in USD so far
+    pub fn total_cost(&self) -> f64 {
+        lock(&self.report).total.cost_usd
+    }
+
+    /// Fail with [`SkynetError::SpendLimitExceeded`] once spend reaches the limit
+    pub fn check_limit(&self) -> Result<()> {
+        match self.spend_limit_usd {
+            Some(limit_usd) => {
+                let spent_usd = self.total_cost();
+                if spent_usd >= limit_usd {
+                    return Err(SkynetError::SpendLimitExceeded {
+                        spent_usd,
+                        limit_usd,
+                    });
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pricing(input_per_mtok: f64, output_per_mtok: f64) -> ModelPricing {
+        ModelPricing {
+            input_per_mtok,
+            output_per_mtok,
+            ..ModelPricing::default()
+        }
+    }
+
+    fn usage(input_tokens: u32, output_tokens: u32) -> Usage {
+        Usage {
+            input_tokens,
+            output_tokens,
+            ..Usage::default()
+        }
+    }
+
+    fn tracker(spend_limit_usd: Option<f64>) -> UsageTracker {
+        let prices = BTreeMap::from([
+            ("claude-3".to_string(), pricing(1.0, 1.0)),
+            ("claude-3-5-sonnet".to_string(), pricing(3.0, 15.0)),
+            ("claude-3-5-sonnet-20241022".to_string(), pricing(2.0, 10.0)),
+        ]);
+        UsageTracker::new(prices, spend_limit_usd)
+    }
+
+    #[test]
+    fn prices_by_exact_name_then_longest_prefix() {
+        let tracker = tracker(None);
+        let price = |model| tracker.pricing(model).map(|p| p.input_per_mtok);
+
+        assert_eq!(price("claude-3-5-sonnet-20241022"), Some(2.0));
+        assert_eq!(price("claude-3-5-sonnet-20240620"), Some(3.0));
+        assert_eq!(price("claude-3-opus-20240229"), Some(1.0));
+        assert_eq!(price("gpt-4o"), None);
+    }
+
+    #[test]
+    fn costs_every_kind_of_token() {
+        let pricing = ModelPricing {
+            input_per_mtok: 3.0,
+            output_per_mtok: 15.0,
+            cache_write_per_mtok: 3.75,
+            cache_read_per_mtok: 0.3,
+        };
+        let usage = Usage {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_creation_input_tokens: 200_000,
+            cache_read_input_tokens: 500_000,
+        };
+        assert!((pricing.cost(&usage) - (3.0 + 1.5 + 0.75 + 0.15)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn aggregates_per_session_and_provider() {
+        let tracker = tracker(None);
+        tracker.record(
+            "a",
+            "anthropic",
+            "claude-3-5-sonnet-latest",
+            &usage(1_000_000, 0),
+        );
+        tracker.record("a", "ollama", "llama3", &usage(500, 100));
+        tracker.record(
+            "b",
+            "anthropic",
+            "claude-3-5-sonnet-latest",
+            &usage(0, 1_000_000),
+        );
+
+        let report = tracker.report();
+        assert_eq!(report.total.requests, 3);
+        assert_eq!(report.total.usage, usage(1_000_500, 1_000_100));
+        assert_eq!(report.total.cost_usd, 18.0);
+
+        assert_eq!(report.by_session["a"].requests, 2);
+        assert_eq!(report.by_session["a"].cost_usd, 3.0);
+        assert_eq!(report.by_session["b"].cost_usd, 15.0);
+
+        assert_eq!(report.by_provider["anthropic"].requests, 2);
+        assert_eq!(report.by_provider["anthropic"].cost_usd, 18.0);
+        assert_eq!(report.by_provider["ollama"].usage, usage(500, 100));
+        assert_eq!(report.by_provider["ollama"].cost_usd, 0.0);
+    }
+
+    #[test]
+    fn refuses_requests_once_spend_reaches_the_limit() {
+        let tracker = tracker(Some(5.0));
+        assert!(tracker.check_limit().is_ok());
+
+        tracker.record("a", "anthropic", "claude-3-5-sonnet", &usage(1_000_000, 0));
+        assert!(tracker.check_limit().is_ok());
+
+        tracker.record("a", "anthropic", "claude-3-5-sonnet", &usage(0, 200_000));
+        match tracker.check_limit() {
+            Err(SkynetError::SpendLimitExceeded {
+                spent_usd,
+                limit_usd,
+            }) => {
+                assert_eq!(spent_usd, 6.0);
+                assert_eq!(limit_usd, 5.0);
+            }
+            other => panic!("expected the spend limit error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn has_no_limit_unless_configured() {
+        let tracker = tracker(None);
+        tracker.record("a", "anthropic", "claude-3-5-sonnet", &usage(0, 10_000_000));
+        assert!(tracker.check_limit().is_ok());
+    }
+}
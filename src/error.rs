@@ -0,0 +1,132 @@
+//! Crate-wide error type
+
+use crate::providers::ProviderError;
+use thiserror::Error;
+
+/// Boxed error used as the source of a [`SkynetError`]
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors returned throughout the SKYNET framework.
+///
+/// Every variant keeps the underlying error as its `source()`, so the full
+/// chain can be logged; [`is_retryable`](Self::is_retryable) tells callers
+/// whether trying the same operation again may succeed.
+#[derive(Debug, Error)]
+pub enum SkynetError {
+    /// Configuration could not be loaded or is invalid
+    #[error("configuration error: {message}")]
+    Config {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    /// An LLM provider call failed
+    #[error(transparent)]
+    Provider(#[from] ProviderError),
+
+    /// A memory backend failed to store or load messages
+    #[error("memory error: {message}")]
+    Memory {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    /// A tool failed while executing
+    #[error("tool '{tool}' failed: {message}")]
+    Tool {
+        tool: String,
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    /// The configured spend limit has been reached
+    #[error("spend limit of ${limit_usd:.2} reached (spent ${spent_usd:.4})")]
+    SpendLimitExceeded { spent_usd: f64, limit_usd: f64 },
+
+    /// The agent loop itself failed
+    #[error("agent error: {message}")]
+    Agent {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+}
+
+impl SkynetError {
+    /// Configuration error with no underlying cause
+    pub fn config(message: impl Into<String>) -> Self {
+        Self::Config {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Memory backend error with no underlying cause
+    pub fn memory(message: impl Into<String>) -> Self {
+        Self::Memory {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Error raised by the tool named `tool`
+    pub fn tool(tool: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::Tool {
+            tool: tool.into(),
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Agent loop error with no underlying cause
+    pub fn agent(message: impl Into<String>) -> Self {
+        Self::Agent {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Attach the underlying cause; provider errors keep their own source
+    pub fn with_source(mut self, cause: impl Into<BoxError>) -> Self {
+        match &mut self {
+            Self::Config { source, .. }
+            | Self::Memory { source, .. }
+            | Self::Tool { source, .. }
+            | Self::Agent { source, .. } => *source = Some(cause.into()),
+            Self::Provider(_) | Self::SpendLimitExceeded { .. } => {}
+        }
+        self
+    }
+
+    /// Whether retrying the failed operation may succeed.
+    ///
+    /// Provider rate limits, overloads, network failures and 5xx responses
+    /// are retryable, as are SQLite busy/locked errors. Configuration, tool
+    /// agent and spend-limit errors never are.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Provider(error) => error.is_retryable(),
+            Self::Memory {
+                source: Some(source),
+                ..
+            } => matches!(
+                source.downcast_ref::<rusqlite::Error>(),
+                Some(rusqlite::Error::SqliteFailure(failure, _))
+                    if matches!(
+                        failure.code,
+                        rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked
+                    )
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for SkynetError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::memory(error.to_string()).with_source(error)
+    }
+}
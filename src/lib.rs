@@ -11,15 +11,20 @@
 
 pub mod config;
 pub mod core;
+pub mod error;
+pub mod memory;
 pub mod providers;
 pub mod skynet;
 
 pub use config::Config;
 pub use core::agent::SkynetAgent;
-pub use core::message::{Message, MessageType, Role};
+pub use core::channel::{AgentHandle, ChannelSink, LogSink, OutputSink};
+pub use core::message::{Message, MessageType, Role, ToolCall};
+pub use core::session::{Session, SessionManager};
+pub use error::SkynetError;
 
 /// Result type used throughout the SKYNET framework
-pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+pub type Result<T> = std::result::Result<T, SkynetError>;
 
 /// Version information
-pub const VERSION: &str = env!("CARGO_PKG_VERSION");
\ No newline at end of file
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -1,33 +1,26 @@
-use skynet_rust::{Config, Result, SkynetAgent};
-use tracing::{info, error};
-use tracing_subscriber;
+mod cli;
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
+use clap::Parser;
+use cli::logging::Logging;
+use cli::Cli;
+use tracing::info;
 
-    info!("🤖 SKYNET-RUST v{} starting...", skynet_rust::VERSION);
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
 
-    // Load configuration
-    let config = Config::load().await?;
-    info!("Configuration loaded successfully");
+    // Initialize tracing; the configured level applies once it is loaded
+    let logging = Logging::init();
 
-    // Create and initialize the agent
-    let mut agent = SkynetAgent::new(config).await?;
-    info!("SKYNET agent initialized");
+    info!("🤖 SKYNET-RUST v{} starting...", skynet_rust::VERSION);
 
-    // Start the main agent loop
-    info!("🚀 Starting agent loop...");
-    match agent.run().await {
-        Ok(_) => info!("Agent shutdown gracefully"),
-        Err(e) => {
-            error!("Agent error: {}", e);
-            std::process::exit(1);
+    if let Err(e) = cli.execute(&logging).await {
+        eprintln!("error: {}", e);
+        let mut source = std::error::Error::source(&e);
+        while let Some(cause) = source {
+            eprintln!("  caused by: {}", cause);
+            source = cause.source();
         }
+        std::process::exit(1);
     }
-
-    Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,137 @@
+use crate::core::agent::Memory;
+use crate::core::message::Message;
+use crate::core::session::Session;
+use crate::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::debug;
+
+/// Simple in-memory storage implementation for testing
+#[derive(Default)]
+pub struct InMemoryStorage {
+    messages: Arc<RwLock<Vec<Message>>>,
+    sessions: Arc<RwLock<BTreeMap<String, Session>>>,
+}
+
+impl InMemoryStorage {
+    /// Create an empty in-memory store
+    pub fn new() -> Self {
+        Self {
+            messages: Arc::new(RwLock::new(Vec::new())),
+            sessions: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl Memory for InMemoryStorage {
+    /// Replaces a stored message with the same ID, like `SqliteMemory`
+    async fn store(&self, message: &Message) -> Result<()> {
+        let mut messages = self.messages.write().await;
+        match messages.iter_mut().find(|stored| stored.id == message.id) {
+            Some(stored) => *stored = message.clone(),
+            None => messages.push(message.clone()),
+        }
+        debug!("Stored message: {}", message.id);
+        Ok(())
+    }
+
+    async fn retrieve(&self, session_id: &str, limit: usize) -> Result<Vec<Message>> {
+        let messages = self.messages.read().await;
+        let session: Vec<&Message> = messages
+            .iter()
+            .filter(|msg| msg.session() == session_id)
+            .collect();
+        let start = if session.len() > limit {
+            session.len() - limit
+        } else {
+            0
+        };
+        Ok(session[start..].iter().map(|msg| (*msg).clone()).collect())
+    }
+
+    /// Drops the replaced messages; this store is volatile, so they are gone
+    async fn compact(&self, summary: &Message) -> Result<()> {
+        let replaced = summary.summary_of();
+        let mut messages = self.messages.write().await;
+
+        let position = messages
+            .iter()
+            .position(|message| replaced.contains(&message.id))
+            .unwrap_or(messages.len());
+        messages.retain(|message| !replaced.contains(&message.id));
+        let position = position.min(messages.len());
+        messages.insert(position, summary.clone());
+
+        debug!("Compacted {} messages into {}", replaced.len(), summary.id);
+        Ok(())
+    }
+
+    async fn save_session(&self, session: &Session) -> Result<()> {
+        self.sessions
+            .write()
+            .await
+            .insert(session.id.clone(), session.clone());
+        Ok(())
+    }
+
+    async fn sessions(&self) -> Result<Vec<Session>> {
+        Ok(self.sessions.read().await.values().cloned().collect())
+    }
+
+    async fn delete_session(&self, id: &str) -> Result<()> {
+        self.messages
+            .write()
+            .await
+            .retain(|msg| msg.session() != id);
+        self.sessions.write().await.remove(id);
+        debug!("Deleted session: {}", id);
+        Ok(())
+    }
+
+    async fn prune(&self, before: DateTime<Utc>, session_id: Option<&str>) -> Result<usize> {
+        let mut messages = self.messages.write().await;
+        let count = messages.len();
+        messages.retain(|msg| {
+            msg.timestamp >= before || session_id.is_some_and(|id| msg.session() != id)
+        });
+        let pruned = count - messages.len();
+        debug!("Pruned {} messages", pruned);
+        Ok(pruned)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Message>> {
+        let messages = self.messages.read().await;
+        let results: Vec<Message> = messages
+            .iter()
+            .filter(|msg| msg.content.to_lowercase().contains(&query.to_lowercase()))
+            .cloned()
+            .collect();
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn storing_an_existing_id_replaces_the_message() {
+        let memory = InMemoryStorage::new();
+        let mut message = Message::user("first".to_string());
+        memory.store(&message).await.unwrap();
+        memory
+            .store(&Message::user("second".to_string()))
+            .await
+            .unwrap();
+
+        message.content = "edited".to_string();
+        memory.store(&message).await.unwrap();
+        let history = memory.retrieve("default", 10).await.unwrap();
+        let contents: Vec<&str> = history.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["edited", "second"]);
+    }
+}
@@ -0,0 +1,22 @@
+//! Memory backends
+
+pub mod in_memory;
+pub mod search;
+pub mod sqlite;
+
+pub use crate::core::agent::Memory;
+pub use in_memory::InMemoryStorage;
+pub use search::{SearchHit, SearchQuery};
+pub use sqlite::SqliteMemory;
+
+use crate::config::{DatabaseConfig, MemoryBackend};
+use crate::Result;
+use std::sync::Arc;
+
+/// Build the memory backend selected by the database configuration
+pub fn from_config(config: &DatabaseConfig) -> Result<Arc<dyn Memory>> {
+    match config.backend {
+        MemoryBackend::Sqlite => Ok(Arc::new(SqliteMemory::open(&config.path)?)),
+        MemoryBackend::Memory => Ok(Arc::new(InMemoryStorage::new())),
+    }
+}
@@ -0,0 +1,175 @@
+use crate::core::message::{Message, MessageType, Role};
+use chrono::{DateTime, Utc};
+
+/// Filtered, ranked search over stored messages
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    /// Search text. Words are matched as terms, `"quoted text"` as a phrase
+    /// and a trailing `*` makes a word a prefix query.
+    pub text: String,
+
+    /// Only return messages with one of these roles (empty = any)
+    pub roles: Vec<Role>,
+
+    /// Only return messages of one of these types (empty = any)
+    pub message_types: Vec<MessageType>,
+
+    /// Only return messages at or after this time
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only return messages at or before this time
+    pub until: Option<DateTime<Utc>>,
+
+    /// Maximum number of hits to return
+    pub limit: Option<usize>,
+
+    /// Match messages containing any term instead of all of them
+    pub match_any: bool,
+
+    /// Skip messages that were compacted into a summary
+    pub active_only: bool,
+
+    /// Only return messages from this session
+    pub session_id: Option<String>,
+}
+
+impl SearchQuery {
+    /// Create a query for the given text with no filters
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Restrict results to the given roles
+    pub fn with_roles(mut self, roles: Vec<Role>) -> Self {
+        self.roles = roles;
+        self
+    }
+
+    /// Restrict results to the given message types
+    pub fn with_message_types(mut self, message_types: Vec<MessageType>) -> Self {
+        self.message_types = message_types;
+        self
+    }
+
+    /// Restrict results to the given time range
+    pub fn between(mut self, since: Option<DateTime<Utc>>, until: Option<DateTime<Utc>>) -> Self {
+        self.since = since;
+        self.until = until;
+        self
+    }
+
+    /// Match messages containing any of the terms, ranking those with more
+    /// matches higher
+    pub fn matching_any(mut self) -> Self {
+        self.match_any = true;
+        self
+    }
+
+    /// Skip messages that were compacted into a summary
+    pub fn active_only(mut self) -> Self {
+        self.active_only = true;
+        self
+    }
+
+    /// Restrict results to the session with `id`
+    pub fn in_session(mut self, id: &str) -> Self {
+        self.session_id = Some(id.to_string());
+        self
+    }
+
+    /// Limit the number of hits
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Whether a message passes the session, role, type and time filters
+    pub fn matches_filters(&self, message: &Message) -> bool {
+        self.session_id.iter().all(|id| message.session() == id)
+            && (self.roles.is_empty() || self.roles.contains(&message.role))
+            && (self.message_types.is_empty() || self.message_types.contains(&message.message_type))
+            && self.since.iter().all(|since| message.timestamp >= *since)
+            && self.until.iter().all(|until| message.timestamp <= *until)
+    }
+}
+
+/// A single search result
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub message: Message,
+
+    /// Relevance score; higher is better
+    pub score: f64,
+
+    /// Excerpt of the content; indexed backends wrap matches in `**`
+    pub snippet: String,
+}
+
+/// Maximum length of a snippet produced without a full-text index
+const EXCERPT_CHARS: usize = 160;
+
+/// Plain excerpt of a message's content, used when no index can highlight
+pub(crate) fn excerpt(content: &str) -> String {
+    if content.chars().count() <= EXCERPT_CHARS {
+        return content.to_string();
+    }
+    let mut excerpt: String = content.chars().take(EXCERPT_CHARS).collect();
+    excerpt.push('…');
+    excerpt
+}
+
+/// A parsed search term
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SearchTerm {
+    Word(String),
+    Phrase(String),
+    Prefix(String),
+}
+
+impl SearchTerm {
+    /// The text of the term, without quotes or `*`
+    pub(crate) fn text(&self) -> &str {
+        match self {
+            Self::Word(text) | Self::Phrase(text) | Self::Prefix(text) => text,
+        }
+    }
+}
+
+/// Split search text into words, `"phrases"` and `prefix*` terms
+pub(crate) fn parse_terms(text: &str) -> Vec<SearchTerm> {
+    let mut terms = Vec::new();
+    let mut rest = text.trim();
+
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let (phrase, remainder) = match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            };
+            if !phrase.trim().is_empty() {
+                terms.push(SearchTerm::Phrase(phrase.trim().to_string()));
+            }
+            rest = remainder.trim_start();
+            continue;
+        }
+
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '"')
+            .unwrap_or(rest.len());
+        let word = &rest[..end];
+        rest = rest[end..].trim_start();
+
+        match word.strip_suffix('*') {
+            Some(prefix) if !prefix.is_empty() => {
+                terms.push(SearchTerm::Prefix(prefix.to_string()))
+            }
+            Some(_) => {}
+            None => terms.push(SearchTerm::Word(word.to_string())),
+        }
+    }
+
+    terms
+}
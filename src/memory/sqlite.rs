@@ -0,0 +1,836 @@
+use crate::core::agent::Memory;
+use crate::core::message::{Message, MessageType, Role};
+use crate::core::session::Session;
+use crate::memory::search::{self, SearchHit, SearchQuery, SearchTerm};
+use crate::{Result, SkynetError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info};
+
+/// Schema migrations, applied in order. The index of a migration plus one is
+/// the schema version it produces, tracked in SQLite's `user_version`.
+const MIGRATIONS: &[&str] = &[
+    // v1: message log
+    "CREATE TABLE messages (
+        seq          INTEGER PRIMARY KEY AUTOINCREMENT,
+        id           TEXT NOT NULL UNIQUE,
+        role         TEXT NOT NULL,
+        content      TEXT NOT NULL,
+        message_type TEXT NOT NULL,
+        metadata     TEXT NOT NULL DEFAULT '{}',
+        timestamp    TEXT NOT NULL
+    );
+    CREATE INDEX idx_messages_timestamp ON messages(timestamp);",
+    // v2: FTS5 full-text index over message content, kept in sync by triggers
+    "CREATE VIRTUAL TABLE messages_fts USING fts5(
+        content,
+        content = 'messages',
+        content_rowid = 'seq',
+        tokenize = 'unicode61'
+    );
+    INSERT INTO messages_fts(messages_fts) VALUES ('rebuild');
+    CREATE TRIGGER messages_fts_insert AFTER INSERT ON messages BEGIN
+        INSERT INTO messages_fts(rowid, content) VALUES (new.seq, new.content);
+    END;
+    CREATE TRIGGER messages_fts_delete AFTER DELETE ON messages BEGIN
+        INSERT INTO messages_fts(messages_fts, rowid, content)
+            VALUES ('delete', old.seq, old.content);
+    END;
+    CREATE TRIGGER messages_fts_update AFTER UPDATE OF content ON messages BEGIN
+        INSERT INTO messages_fts(messages_fts, rowid, content)
+            VALUES ('delete', old.seq, old.content);
+        INSERT INTO messages_fts(rowid, content) VALUES (new.seq, new.content);
+    END;",
+    // v3: messages replaced by a summary point at it and drop out of history
+    "ALTER TABLE messages ADD COLUMN compacted_into TEXT;
+    CREATE INDEX idx_messages_active ON messages(compacted_into, timestamp);",
+    // v4: sessions; existing history becomes the default session
+    "CREATE TABLE sessions (
+        id         TEXT PRIMARY KEY,
+        title      TEXT,
+        parent_id  TEXT,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        archived   INTEGER NOT NULL DEFAULT 0
+    );
+    ALTER TABLE messages ADD COLUMN session_id TEXT NOT NULL DEFAULT 'default';
+    INSERT INTO sessions (id, created_at, updated_at)
+        SELECT session_id, MIN(timestamp), MAX(timestamp) FROM messages GROUP BY session_id;
+    CREATE INDEX idx_messages_session ON messages(session_id, compacted_into, timestamp);",
+];
+
+const MESSAGE_COLUMNS: &str = "id, role, content, message_type, metadata, timestamp, session_id";
+
+/// Same columns qualified for queries joining the FTS table
+const QUALIFIED_MESSAGE_COLUMNS: &str =
+    "m.id, m.role, m.content, m.message_type, m.metadata, m.timestamp, m.session_id";
+
+const SESSION_COLUMNS: &str = "id, title, parent_id, created_at, updated_at, archived";
+
+/// Number of tokens around a match included in search snippets
+const SNIPPET_TOKENS: u32 = 16;
+
+/// SQLite-backed persistent memory
+pub struct SqliteMemory {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteMemory {
+    /// Open (or create) a database at `path` and bring its schema up to date
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let conn = Connection::open(path.as_ref())?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        info!("Opened SQLite memory at {}", path.as_ref().display());
+        Self::from_connection(conn)
+    }
+
+    /// Open a private in-memory database, mainly useful for tests
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self> {
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Current schema version of the database
+    pub async fn schema_version(&self) -> Result<usize> {
+        self.with_conn(|conn| Ok(user_version(conn)?)).await
+    }
+
+    /// Run a blocking closure against the connection on the blocking pool
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| SkynetError::memory("SQLite memory connection lock poisoned"))?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| SkynetError::memory("SQLite memory task failed").with_source(e))?
+    }
+}
+
+#[async_trait]
+impl Memory for SqliteMemory {
+    async fn store(&self, message: &Message) -> Result<()> {
+        let message = message.clone();
+        self.with_conn(move |conn| {
+            upsert_message(conn, &message)?;
+            debug!("Stored message: {}", message.id);
+            Ok(())
+        })
+        .await
+    }
+
+    /// Most recent messages not compacted into a summary. Ordered by time so
+    /// a summary takes the place of the messages it replaced.
+    async fn retrieve(&self, session_id: &str, limit: usize) -> Result<Vec<Message>> {
+        let session_id = session_id.to_string();
+        self.with_conn(move |conn| {
+            let sql = format!(
+                "SELECT {} FROM messages WHERE session_id = ?1 AND compacted_into IS NULL
+                 ORDER BY timestamp DESC, seq DESC LIMIT ?2",
+                MESSAGE_COLUMNS
+            );
+            // SQLite treats a negative limit as "no limit"
+            let limit = i64::try_from(limit).unwrap_or(-1);
+            let mut messages = query_messages(conn, &sql, params![session_id, limit])?;
+            messages.reverse();
+            Ok(messages)
+        })
+        .await
+    }
+
+    async fn compact(&self, summary: &Message) -> Result<()> {
+        let summary = summary.clone();
+        self.with_conn(move |conn| {
+            let replaced = summary.summary_of();
+            let tx = conn.transaction()?;
+            upsert_message(&tx, &summary)?;
+            {
+                let mut stmt =
+                    tx.prepare("UPDATE messages SET compacted_into = ?1 WHERE id = ?2")?;
+                for id in &replaced {
+                    stmt.execute(params![summary.id, id])?;
+                }
+            }
+            tx.commit()?;
+            debug!("Compacted {} messages into {}", replaced.len(), summary.id);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Message>> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            let sql = format!("SELECT {} FROM messages WHERE id = ?1", MESSAGE_COLUMNS);
+            conn.query_row(&sql, params![id], MessageRow::from_row)
+                .optional()?
+                .map(MessageRow::into_message)
+                .transpose()
+        })
+        .await
+    }
+
+    async fn save_session(&self, session: &Session) -> Result<()> {
+        let session = session.clone();
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO sessions (id, title, parent_id, created_at, updated_at, archived)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(id) DO UPDATE SET
+                    title = excluded.title,
+                    parent_id = excluded.parent_id,
+                    updated_at = excluded.updated_at,
+                    archived = excluded.archived",
+                params![
+                    session.id,
+                    session.title,
+                    session.parent_id,
+                    format_timestamp(&session.created_at),
+                    format_timestamp(&session.updated_at),
+                    session.archived,
+                ],
+            )?;
+            debug!("Saved session: {}", session.id);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn sessions(&self) -> Result<Vec<Session>> {
+        self.with_conn(|conn| {
+            let sql = format!(
+                "SELECT {} FROM sessions ORDER BY updated_at DESC",
+                SESSION_COLUMNS
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map([], SessionRow::from_row)?;
+
+            let mut sessions = Vec::new();
+            for row in rows {
+                sessions.push(row?.into_session()?);
+            }
+            Ok(sessions)
+        })
+        .await
+    }
+
+    async fn session(&self, id: &str) -> Result<Option<Session>> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            let sql = format!("SELECT {} FROM sessions WHERE id = ?1", SESSION_COLUMNS);
+            conn.query_row(&sql, params![id], SessionRow::from_row)
+                .optional()?
+                .map(SessionRow::into_session)
+                .transpose()
+        })
+        .await
+    }
+
+    async fn delete_session(&self, id: &str) -> Result<()> {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let deleted = tx.execute("DELETE FROM messages WHERE session_id = ?1", params![id])?;
+            tx.execute("DELETE FROM sessions WHERE id = ?1", params![id])?;
+            tx.commit()?;
+            debug!("Deleted session {} with {} messages", id, deleted);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn prune(&self, before: DateTime<Utc>, session_id: Option<&str>) -> Result<usize> {
+        let before = format_timestamp(&before);
+        let session_id = session_id.map(str::to_string);
+        self.with_conn(move |conn| {
+            let pruned = conn.execute(
+                "DELETE FROM messages WHERE timestamp < ?1 AND (?2 IS NULL OR session_id = ?2)",
+                params![before, session_id],
+            )?;
+            debug!("Pruned {} messages", pruned);
+            Ok(pruned)
+        })
+        .await
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Message>> {
+        let hits = self.search_with(&SearchQuery::new(query)).await?;
+        Ok(hits.into_iter().map(|hit| hit.message).collect())
+    }
+
+    async fn search_with(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        let query = query.clone();
+        self.with_conn(move |conn| search_messages(conn, &query))
+            .await
+    }
+}
+
+/// Insert a message, or update it if its ID is already stored
+fn upsert_message(conn: &Connection, message: &Message) -> Result<()> {
+    conn.execute(
+        "INSERT INTO messages (id, role, content, message_type, metadata, timestamp, session_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(id) DO UPDATE SET
+            role = excluded.role,
+            content = excluded.content,
+            message_type = excluded.message_type,
+            metadata = excluded.metadata,
+            timestamp = excluded.timestamp,
+            session_id = excluded.session_id",
+        params![
+            message.id,
+            enum_to_text(&message.role)?,
+            message.content,
+            enum_to_text(&message.message_type)?,
+            serde_json::to_string(&message.metadata).map_err(|e| {
+                SkynetError::memory("Failed to encode message metadata").with_source(e)
+            })?,
+            format_timestamp(&message.timestamp),
+            message.session(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Run a ranked FTS5 search, or a filtered recency listing for empty text
+fn search_messages(conn: &Connection, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+    let expression = fts_expression(&query.text, query.match_any);
+    let mut conditions = Vec::new();
+    let mut values: Vec<SqlValue> = Vec::new();
+
+    if let Some(expression) = &expression {
+        conditions.push("messages_fts MATCH ?".to_string());
+        values.push(SqlValue::Text(expression.clone()));
+    }
+    if !query.roles.is_empty() {
+        conditions.push(format!("m.role IN ({})", placeholders(query.roles.len())));
+        for role in &query.roles {
+            values.push(SqlValue::Text(enum_to_text(role)?));
+        }
+    }
+    if !query.message_types.is_empty() {
+        conditions.push(format!(
+            "m.message_type IN ({})",
+            placeholders(query.message_types.len())
+        ));
+        for message_type in &query.message_types {
+            values.push(SqlValue::Text(enum_to_text(message_type)?));
+        }
+    }
+    if let Some(since) = &query.since {
+        conditions.push("m.timestamp >= ?".to_string());
+        values.push(SqlValue::Text(format_timestamp(since)));
+    }
+    if let Some(until) = &query.until {
+        conditions.push("m.timestamp <= ?".to_string());
+        values.push(SqlValue::Text(format_timestamp(until)));
+    }
+    if let Some(session_id) = &query.session_id {
+        conditions.push("m.session_id = ?".to_string());
+        values.push(SqlValue::Text(session_id.clone()));
+    }
+    if query.active_only {
+        conditions.push("m.compacted_into IS NULL".to_string());
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let limit = query.limit.map(|limit| limit as i64).unwrap_or(-1);
+
+    let sql = if expression.is_some() {
+        format!(
+            "SELECT {}, -bm25(messages_fts) AS score,
+                    snippet(messages_fts, 0, '**', '**', '…', {}) AS snippet
+             FROM messages_fts JOIN messages m ON m.seq = messages_fts.rowid
+             {} ORDER BY bm25(messages_fts), m.seq DESC LIMIT {}",
+            QUALIFIED_MESSAGE_COLUMNS, SNIPPET_TOKENS, where_clause, limit
+        )
+    } else {
+        format!(
+            "SELECT {}, 0.0 AS score, NULL AS snippet
+             FROM messages m {} ORDER BY m.seq DESC LIMIT {}",
+            QUALIFIED_MESSAGE_COLUMNS, where_clause, limit
+        )
+    };
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_from_iter(values), |row| {
+        Ok((
+            MessageRow::from_row(row)?,
+            row.get::<_, f64>(7)?,
+            row.get::<_, Option<String>>(8)?,
+        ))
+    })?;
+
+    let mut hits = Vec::new();
+    for row in rows {
+        let (message, score, snippet) = row?;
+        let message = message.into_message()?;
+        hits.push(SearchHit {
+            snippet: snippet.unwrap_or_else(|| search::excerpt(&message.content)),
+            score,
+            message,
+        });
+    }
+    Ok(hits)
+}
+
+/// Translate search text into an FTS5 match expression.
+///
+/// Every term is quoted so user input can never produce FTS5 syntax errors;
+/// terms are AND-ed, or OR-ed when `any` is set. Returns `None` when there is
+/// nothing to match.
+fn fts_expression(text: &str, any: bool) -> Option<String> {
+    let quote = |term: &str| format!("\"{}\"", term.replace('"', "\"\""));
+
+    let terms: Vec<String> = search::parse_terms(text)
+        .into_iter()
+        .map(|term| match term {
+            SearchTerm::Word(word) | SearchTerm::Phrase(word) => quote(&word),
+            SearchTerm::Prefix(prefix) => format!("{}*", quote(&prefix)),
+        })
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(if any { " OR " } else { " " }))
+    }
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+/// Apply every migration newer than the database's recorded version
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let current = user_version(conn)?;
+    if current > MIGRATIONS.len() {
+        return Err(SkynetError::memory(format!(
+            "SQLite memory schema version {} is newer than supported version {}",
+            current,
+            MIGRATIONS.len()
+        )));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = index + 1;
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", version as i64)?;
+        tx.commit()?;
+        info!("Migrated SQLite memory schema to version {}", version);
+    }
+
+    Ok(())
+}
+
+fn user_version(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+        .map(|version| version as usize)
+}
+
+fn query_messages(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<Message>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params, MessageRow::from_row)?;
+
+    let mut messages = Vec::new();
+    for row in rows {
+        messages.push(row?.into_message()?);
+    }
+    Ok(messages)
+}
+
+/// Raw column values of a row selected with `MESSAGE_COLUMNS`
+struct MessageRow {
+    id: String,
+    role: String,
+    content: String,
+    message_type: String,
+    metadata: String,
+    timestamp: String,
+    session_id: String,
+}
+
+impl MessageRow {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            role: row.get(1)?,
+            content: row.get(2)?,
+            message_type: row.get(3)?,
+            metadata: row.get(4)?,
+            timestamp: row.get(5)?,
+            session_id: row.get(6)?,
+        })
+    }
+
+    fn into_message(self) -> Result<Message> {
+        Ok(Message {
+            id: self.id,
+            role: text_to_enum::<Role>(&self.role)?,
+            content: self.content,
+            message_type: text_to_enum::<MessageType>(&self.message_type)?,
+            metadata: serde_json::from_str(&self.metadata).map_err(|e| {
+                SkynetError::memory("Stored message has invalid metadata").with_source(e)
+            })?,
+            timestamp: parse_timestamp(&self.timestamp, "message")?,
+            session_id: Some(self.session_id),
+        })
+    }
+}
+
+/// Raw column values of a row selected with `SESSION_COLUMNS`
+struct SessionRow {
+    id: String,
+    title: Option<String>,
+    parent_id: Option<String>,
+    created_at: String,
+    updated_at: String,
+    archived: bool,
+}
+
+impl SessionRow {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            parent_id: row.get(2)?,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+            archived: row.get(5)?,
+        })
+    }
+
+    fn into_session(self) -> Result<Session> {
+        Ok(Session {
+            id: self.id,
+            title: self.title,
+            parent_id: self.parent_id,
+            created_at: parse_timestamp(&self.created_at, "session")?,
+            updated_at: parse_timestamp(&self.updated_at, "session")?,
+            archived: self.archived,
+        })
+    }
+}
+
+fn parse_timestamp(text: &str, record: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(text)
+        .map(|timestamp| timestamp.with_timezone(&chrono::Utc))
+        .map_err(|e| {
+            SkynetError::memory(format!("Stored {} has an invalid timestamp", record))
+                .with_source(e)
+        })
+}
+
+/// Fixed-width RFC 3339 so timestamps sort correctly as text
+pub(crate) fn format_timestamp(timestamp: &chrono::DateTime<chrono::Utc>) -> String {
+    timestamp.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+}
+
+/// Store unit enums (`Role`, `MessageType`) by their serde name
+pub(crate) fn enum_to_text<T: serde::Serialize>(value: &T) -> Result<String> {
+    match serde_json::to_value(value)
+        .map_err(|e| SkynetError::memory("Failed to encode enum").with_source(e))?
+    {
+        serde_json::Value::String(text) => Ok(text),
+        other => Err(SkynetError::memory(format!(
+            "expected a unit variant, got {}",
+            other
+        ))),
+    }
+}
+
+fn text_to_enum<T: serde::de::DeserializeOwned>(text: &str) -> Result<T> {
+    serde_json::from_value(serde_json::Value::String(text.to_string())).map_err(|e| {
+        SkynetError::memory(format!("Stored message has unknown value '{}'", text)).with_source(e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::message::ToolCall;
+
+    fn contents(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter()
+            .map(|hit| hit.message.content.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn migrates_a_version_1_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        conn.execute(
+            "INSERT INTO messages (id, role, content, message_type, timestamp)
+             VALUES ('old', 'User', 'written before full-text search', 'Text', ?1)",
+            params![format_timestamp(&Utc::now())],
+        )
+        .unwrap();
+        let memory = SqliteMemory::from_connection(conn).unwrap();
+
+        assert_eq!(memory.schema_version().await.unwrap(), MIGRATIONS.len());
+        // v2 indexes existing rows, v4 moves them into the default session
+        let hits = memory
+            .search_with(&SearchQuery::new("search"))
+            .await
+            .unwrap();
+        assert_eq!(contents(&hits), ["written before full-text search"]);
+        let history = memory.retrieve("default", 10).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(memory.session("default").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn refuses_a_newer_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() as i64 + 1)
+            .unwrap();
+        assert!(SqliteMemory::from_connection(conn).is_err());
+    }
+
+    #[tokio::test]
+    async fn ranks_denser_matches_first() {
+        let memory = SqliteMemory::open_in_memory().unwrap();
+        for content in [
+            "A long note about gardening, cooking, travel and, once, rust.",
+            "rust rust rust",
+            "Nothing relevant here",
+            "rust and async",
+        ] {
+            memory
+                .store(&Message::user(content.to_string()))
+                .await
+                .unwrap();
+        }
+
+        let hits = memory.search_with(&SearchQuery::new("rust")).await.unwrap();
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0].message.content, "rust rust rust");
+        assert!(hits.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        assert!(hits[0].snippet.contains("**rust**"));
+
+        // Terms are AND-ed unless any term may match
+        let hits = memory
+            .search_with(&SearchQuery::new("rust async"))
+            .await
+            .unwrap();
+        assert_eq!(contents(&hits), ["rust and async"]);
+        let hits = memory
+            .search_with(&SearchQuery::new("gardening async").matching_any())
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn triggers_keep_the_index_in_sync() {
+        let memory = SqliteMemory::open_in_memory().unwrap();
+        let mut message = Message::user("alpha".to_string());
+        memory.store(&message).await.unwrap();
+        assert_eq!(memory.search("alpha").await.unwrap().len(), 1);
+
+        // Storing the same ID again updates the row and its index entry
+        message.content = "beta".to_string();
+        memory.store(&message).await.unwrap();
+        assert!(memory.search("alpha").await.unwrap().is_empty());
+        assert_eq!(memory.search("beta").await.unwrap().len(), 1);
+
+        memory
+            .prune(Utc::now() + chrono::Duration::seconds(1), None)
+            .await
+            .unwrap();
+        assert!(memory.search("beta").await.unwrap().is_empty());
+        let rows: i64 = memory
+            .with_conn(|conn| {
+                Ok(conn.query_row("SELECT COUNT(*) FROM messages_fts", [], |row| row.get(0))?)
+            })
+            .await
+            .unwrap();
+        assert_eq!(rows, 0);
+    }
+
+    #[tokio::test]
+    async fn messages_survive_reopening_the_database() {
+        let path = std::env::temp_dir().join(format!("skynet-sqlite-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let timestamp = DateTime::parse_from_rfc3339("2026-10-16T12:34:56.123456Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let question = Message {
+            timestamp,
+            ..Message::user("What's the weather in Oslo?".to_string()).with_session("trip")
+        };
+        let call = Message {
+            timestamp: timestamp + chrono::Duration::seconds(1),
+            ..Message::tool_call(
+                "Let me check.".to_string(),
+                vec![ToolCall {
+                    id: "call-1".to_string(),
+                    name: "weather".to_string(),
+                    arguments: serde_json::json!({ "city": "Oslo", "days": [1, 2] }),
+                }],
+            )
+            .with_metadata("cost_usd".to_string(), serde_json::json!(0.25))
+            .with_session("trip")
+        };
+        {
+            let memory = SqliteMemory::open(&path).unwrap();
+            memory.store(&question).await.unwrap();
+            memory.store(&call).await.unwrap();
+        }
+
+        let memory = SqliteMemory::open(&path).unwrap();
+        let history = memory.retrieve("trip", 10).await;
+        let other = memory.retrieve("default", 10).await;
+        drop(memory);
+        std::fs::remove_file(&path).unwrap();
+
+        let history = history.unwrap();
+        assert_eq!(history.len(), 2);
+        for (stored, loaded) in [&question, &call].into_iter().zip(&history) {
+            assert_eq!(loaded.id, stored.id);
+            assert_eq!(loaded.role, stored.role);
+            assert_eq!(loaded.content, stored.content);
+            assert_eq!(loaded.message_type, stored.message_type);
+            assert_eq!(loaded.metadata, stored.metadata);
+            assert_eq!(loaded.timestamp, stored.timestamp);
+            assert_eq!(loaded.session_id, stored.session_id);
+        }
+        assert_eq!(history[1].tool_calls(), call.tool_calls());
+        assert!(other.unwrap().is_empty());
+    }
+
+    /// In-memory database holding `messages`
+    async fn memory_with(messages: &[Message]) -> SqliteMemory {
+        let memory = SqliteMemory::open_in_memory().unwrap();
+        for message in messages {
+            memory.store(message).await.unwrap();
+        }
+        memory
+    }
+
+    async fn search(memory: &SqliteMemory, query: SearchQuery) -> Vec<String> {
+        let hits = memory.search_with(&query).await.unwrap();
+        contents(&hits).into_iter().map(str::to_string).collect()
+    }
+
+    #[tokio::test]
+    async fn matches_quoted_phrases_in_order() {
+        let memory = memory_with(&[
+            Message::user("the borrow checker rejected it".to_string()),
+            Message::user("checker of borrow rules".to_string()),
+        ])
+        .await;
+
+        let found = search(&memory, SearchQuery::new("\"borrow checker\"")).await;
+        assert_eq!(found, ["the borrow checker rejected it"]);
+        let found = search(&memory, SearchQuery::new("borrow checker")).await;
+        assert_eq!(found.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn matches_prefixes_with_a_trailing_star() {
+        let memory = memory_with(&[
+            Message::user("configuration reloaded".to_string()),
+            Message::user("configure the agent".to_string()),
+            Message::user("a con job".to_string()),
+        ])
+        .await;
+
+        let mut found = search(&memory, SearchQuery::new("config*")).await;
+        found.sort();
+        assert_eq!(found, ["configuration reloaded", "configure the agent"]);
+        assert!(search(&memory, SearchQuery::new("config")).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn highlights_matches_in_snippets() {
+        let memory = memory_with(&[Message::user(
+            "Ferris the crab is the Rust mascot".to_string(),
+        )])
+        .await;
+
+        let hits = memory
+            .search_with(&SearchQuery::new("crab mascot"))
+            .await
+            .unwrap();
+        assert_eq!(
+            hits[0].snippet,
+            "Ferris the **crab** is the Rust **mascot**"
+        );
+    }
+
+    #[tokio::test]
+    async fn filters_by_role() {
+        let memory = memory_with(&[
+            Message::user("tea or coffee?".to_string()),
+            Message::assistant("coffee, always".to_string()),
+        ])
+        .await;
+
+        let query = SearchQuery::new("coffee").with_roles(vec![Role::Assistant]);
+        assert_eq!(search(&memory, query).await, ["coffee, always"]);
+    }
+
+    #[tokio::test]
+    async fn filters_by_message_type() {
+        let memory = memory_with(&[
+            Message::user("look up the forecast".to_string()),
+            Message::tool_result("call-1".to_string(), "forecast: rain".to_string(), false),
+        ])
+        .await;
+
+        let query = SearchQuery::new("forecast").with_message_types(vec![MessageType::ToolResult]);
+        assert_eq!(search(&memory, query).await, ["forecast: rain"]);
+    }
+
+    #[tokio::test]
+    async fn filters_by_time_range() {
+        let now = Utc::now();
+        let at = |days: i64, content: &str| Message {
+            timestamp: now - chrono::Duration::days(days),
+            ..Message::user(content.to_string())
+        };
+        let memory = memory_with(&[
+            at(10, "release planning, early"),
+            at(5, "release planning, middle"),
+            at(1, "release planning, late"),
+        ])
+        .await;
+
+        let since = Some(now - chrono::Duration::days(7));
+        let until = Some(now - chrono::Duration::days(3));
+        let query = SearchQuery::new("release").between(since, until);
+        assert_eq!(search(&memory, query).await, ["release planning, middle"]);
+        let query = SearchQuery::new("release").between(since, None);
+        assert_eq!(search(&memory, query).await.len(), 2);
+    }
+}
@@ -1,97 +1,250 @@
-use crate::core::message::{Message, Role};
-use crate::providers::LLMProvider;
+use crate::config::Secret;
+use crate::core::message::{Message, Role, ToolCall};
+use crate::providers::error::ProviderError;
+use crate::providers::sse::{self, SseEvent};
+use crate::providers::{
+    CompletionRequest, GenerationOptions, LLMProvider, ResponseStream, StreamEvent, ToolDefinition,
+    Usage,
+};
 use crate::Result;
 use async_trait::async_trait;
+use futures_util::stream::{self, StreamExt};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tracing::{debug, error};
 
+/// Model used when none is configured
+pub const DEFAULT_ANTHROPIC_MODEL: &str = "claude-3-sonnet-20240229";
+
+/// `max_tokens` is mandatory for the Messages API; used when none is configured
+const DEFAULT_MAX_TOKENS: u32 = 1000;
+
 /// Anthropic Claude provider
 pub struct AnthropicProvider {
     client: Client,
-    api_key: String,
+    api_key: Secret,
     model: String,
     base_url: String,
+    defaults: GenerationOptions,
 }
 
 impl AnthropicProvider {
     /// Create a new Anthropic provider
-    pub fn new(api_key: String) -> Result<Self> {
+    pub fn new(api_key: impl Into<Secret>) -> Result<Self> {
         let client = Client::new();
-        let model = "claude-3-sonnet-20240229".to_string();
+        let model = DEFAULT_ANTHROPIC_MODEL.to_string();
         let base_url = "https://api.anthropic.com".to_string();
 
         Ok(Self {
             client,
-            api_key,
+            api_key: api_key.into(),
             model,
             base_url,
+            defaults: GenerationOptions::default(),
         })
     }
 
-    /// Convert internal messages to Anthropic API format
+    /// Set the default generation options; a `model` here replaces the
+    /// provider's model
+    pub fn with_options(mut self, options: GenerationOptions) -> Self {
+        if let Some(model) = &options.model {
+            self.model = model.clone();
+        }
+        self.defaults = options;
+        self
+    }
+
+    /// Use a different API endpoint, e.g. a proxy or a test server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Convert internal messages to Anthropic API format.
+    ///
+    /// Tool results are sent as `user` turns, and consecutive turns with the
+    /// same role are merged so the conversation keeps alternating.
     fn convert_messages(&self, messages: &[Message]) -> Vec<AnthropicMessage> {
-        messages
-            .iter()
-            .filter(|msg| matches!(msg.role, Role::User | Role::Assistant))
-            .map(|msg| AnthropicMessage {
-                role: match msg.role {
-                    Role::User => "user".to_string(),
-                    Role::Assistant => "assistant".to_string(),
-                    _ => "user".to_string(), // fallback
-                },
-                content: msg.content.clone(),
-            })
-            .collect()
-    }
-
-    /// Extract system messages
+        let mut converted: Vec<AnthropicMessage> = Vec::new();
+
+        for msg in messages {
+            let (role, blocks) = match msg.role {
+                Role::System => continue,
+                Role::User => ("user", text_block(&msg.content)),
+                Role::Assistant => {
+                    let mut blocks = text_block(&msg.content);
+                    blocks.extend(msg.tool_calls().into_iter().map(|call| {
+                        AnthropicContentBlock::ToolUse {
+                            id: call.id,
+                            name: call.name,
+                            input: call.arguments,
+                        }
+                    }));
+                    ("assistant", blocks)
+                }
+                Role::Tool => (
+                    "user",
+                    vec![AnthropicContentBlock::ToolResult {
+                        tool_use_id: msg.tool_call_id().unwrap_or_default().to_string(),
+                        content: msg.content.clone(),
+                        is_error: msg.is_error(),
+                    }],
+                ),
+            };
+
+            if blocks.is_empty() {
+                continue;
+            }
+
+            match converted.last_mut() {
+                Some(last) if last.role == role => last.content.extend(blocks),
+                _ => converted.push(AnthropicMessage {
+                    role: role.to_string(),
+                    content: blocks,
+                }),
+            }
+        }
+
+        converted
+    }
+
+    /// Join every system message, in order, into the `system` prompt; the
+    /// Messages API takes no system turns in `messages`
     fn extract_system_message(&self, messages: &[Message]) -> Option<String> {
-        messages
+        let system: Vec<&str> = messages
             .iter()
-            .find(|msg| matches!(msg.role, Role::System))
-            .map(|msg| msg.content.clone())
+            .filter(|msg| matches!(msg.role, Role::System) && !msg.content.is_empty())
+            .map(|msg| msg.content.as_str())
+            .collect();
+        (!system.is_empty()).then(|| system.join("\n\n"))
+    }
+
+    /// Build a request body for the Messages API
+    fn build_request(&self, request: &CompletionRequest, stream: bool) -> AnthropicRequest {
+        let options = request.options.with_defaults(&self.defaults);
+
+        AnthropicRequest {
+            model: options.model.unwrap_or_else(|| self.model.clone()),
+            max_tokens: options.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            messages: self.convert_messages(&request.messages),
+            system: self.extract_system_message(&request.messages),
+            tools: request.tools.iter().map(AnthropicTool::from).collect(),
+            temperature: options.temperature,
+            top_p: options.top_p,
+            stop_sequences: options.stop_sequences,
+            metadata: options.metadata,
+            stream,
+        }
+    }
+
+    /// Add the API key and version headers every request needs
+    fn authorize(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        builder
+            .header("x-api-key", self.api_key.expose())
+            .header("anthropic-version", "2023-06-01")
+    }
+
+    /// Send a request and return the raw response.
+    ///
+    /// Non-2xx responses are parsed from the API's error JSON into a
+    /// [`ProviderError`].
+    async fn send(&self, request: &AnthropicRequest) -> Result<reqwest::Response> {
+        let response = self
+            .authorize(self.client.post(format!("{}/v1/messages", self.base_url)))
+            .header("Content-Type", "application/json")
+            .json(request)
+            .send()
+            .await
+            .map_err(ProviderError::from)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let error_text = response.text().await.map_err(ProviderError::from)?;
+            error!("Anthropic API error ({}): {}", status, error_text);
+
+            let error = match serde_json::from_str::<AnthropicErrorResponse>(&error_text) {
+                Ok(body) => ProviderError::from_response(
+                    status,
+                    &headers,
+                    Some(&body.error.kind),
+                    body.error.message,
+                ),
+                Err(_) => ProviderError::from_response(status, &headers, None, error_text),
+            };
+            return Err(error.into());
+        }
+
+        Ok(response)
     }
 }
 
 #[async_trait]
 impl LLMProvider for AnthropicProvider {
-    async fn generate(&self, messages: &[Message]) -> Result<String> {
+    async fn complete(&self, request: &CompletionRequest) -> Result<Message> {
         debug!("Generating response with Anthropic Claude");
 
-        let api_messages = self.convert_messages(messages);
-        let system_message = self.extract_system_message(messages);
+        let body = self.build_request(request, false);
+        let response = self.send(&body).await?;
+        let anthropic_response: AnthropicResponse =
+            response.json().await.map_err(ProviderError::from)?;
 
-        let request = AnthropicRequest {
-            model: self.model.clone(),
-            max_tokens: 1000,
-            messages: api_messages,
-            system: system_message,
-        };
+        if anthropic_response.content.is_empty() {
+            return Err(ProviderError::InvalidResponse {
+                message: "No content in Anthropic response".to_string(),
+            }
+            .into());
+        }
 
-        let response = self
-            .client
-            .post(&format!("{}/v1/messages", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .header("anthropic-version", "2023-06-01")
-            .json(&request)
-            .send()
-            .await?;
+        let model = anthropic_response
+            .model
+            .unwrap_or_else(|| body.model.clone());
+        let usage = Usage::from(anthropic_response.usage);
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("Anthropic API error: {}", error_text);
-            return Err(format!("Anthropic API error: {}", error_text).into());
+        let mut text = String::new();
+        let mut calls = Vec::new();
+        for block in anthropic_response.content {
+            match block {
+                AnthropicContent::Text { text: chunk } => text.push_str(&chunk),
+                AnthropicContent::ToolUse { id, name, input } => calls.push(ToolCall {
+                    id,
+                    name,
+                    arguments: input,
+                }),
+                AnthropicContent::Other => {}
+            }
         }
 
-        let anthropic_response: AnthropicResponse = response.json().await?;
-        
-        if let Some(content) = anthropic_response.content.first() {
-            Ok(content.text.clone())
+        let message = if calls.is_empty() {
+            Message::assistant(text)
         } else {
-            Err("No content in Anthropic response".into())
-        }
+            Message::tool_call(text, calls)
+        };
+        Ok(message.with_usage(&model, &usage))
+    }
+
+    async fn generate_stream(&self, request: &CompletionRequest) -> Result<ResponseStream> {
+        debug!("Streaming response with Anthropic Claude");
+
+        let body = self.build_request(request, true);
+        let response = self.send(&body).await?;
+
+        let mut state = StreamState::default();
+        let bytes = response
+            .bytes_stream()
+            .map(|chunk| chunk.map_err(ProviderError::from));
+        let events = sse::event_stream(bytes)
+            .map(move |event| event.and_then(|event| state.handle(&event)))
+            .flat_map(|result| {
+                let items: Vec<Result<StreamEvent>> = match result {
+                    Ok(events) => events.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(items)
+            });
+
+        Ok(Box::pin(events))
     }
 
     fn name(&self) -> &str {
@@ -102,9 +255,17 @@ impl LLMProvider for AnthropicProvider {
         &self.model
     }
 
+    /// Healthy if the API is reachable and accepts the API key
     async fn health_check(&self) -> Result<bool> {
-        // Simple health check - could be improved
-        Ok(true)
+        let response = self
+            .authorize(self.client.get(format!("{}/v1/models", self.base_url)))
+            .send()
+            .await
+            .map_err(ProviderError::from)?;
+        if !response.status().is_success() {
+            debug!("Anthropic health check failed: {}", response.status());
+        }
+        Ok(response.status().is_success())
     }
 }
 
@@ -115,20 +276,271 @@ struct AnthropicRequest {
     messages: Vec<AnthropicMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     system: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<AnthropicTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 #[derive(Serialize)]
 struct AnthropicMessage {
     role: String,
-    content: String,
+    content: Vec<AnthropicContentBlock>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicContentBlock {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        is_error: bool,
+    },
+}
+
+/// Wrap non-empty text in a content block; the API rejects empty text blocks
+fn text_block(text: &str) -> Vec<AnthropicContentBlock> {
+    if text.is_empty() {
+        Vec::new()
+    } else {
+        vec![AnthropicContentBlock::Text {
+            text: text.to_string(),
+        }]
+    }
+}
+
+#[derive(Serialize)]
+struct AnthropicTool {
+    name: String,
+    description: String,
+    input_schema: serde_json::Value,
+}
+
+impl From<&ToolDefinition> for AnthropicTool {
+    fn from(tool: &ToolDefinition) -> Self {
+        Self {
+            name: tool.name.clone(),
+            description: tool.description.clone(),
+            input_schema: tool.input_schema.clone(),
+        }
+    }
 }
 
 #[derive(Deserialize)]
 struct AnthropicResponse {
     content: Vec<AnthropicContent>,
+    model: Option<String>,
+    #[serde(default)]
+    usage: StreamUsage,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicContent {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Accumulates state across Messages API stream events
+#[derive(Default)]
+struct StreamState {
+    usage: Usage,
+    stop_reason: Option<String>,
+    model: Option<String>,
+}
+
+impl StreamState {
+    /// Translate one server-sent event into zero or more stream events
+    fn handle(&mut self, event: &SseEvent) -> Result<Vec<StreamEvent>> {
+        if event.data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let payload: StreamPayload =
+            serde_json::from_str(&event.data).map_err(ProviderError::from)?;
+        let mut events = Vec::new();
+
+        match payload {
+            StreamPayload::MessageStart { message } => {
+                self.usage = message.usage.into();
+                self.model = message.model;
+            }
+            StreamPayload::ContentBlockStart {
+                index,
+                content_block,
+            } => match content_block {
+                StreamContentBlock::Text { text } if !text.is_empty() => {
+                    events.push(StreamEvent::TextDelta(text));
+                }
+                StreamContentBlock::ToolUse { id, name } => {
+                    events.push(StreamEvent::ToolCallStart { index, id, name });
+                }
+                _ => {}
+            },
+            StreamPayload::ContentBlockDelta { index, delta } => match delta {
+                StreamDelta::TextDelta { text } => events.push(StreamEvent::TextDelta(text)),
+                StreamDelta::InputJsonDelta { partial_json } => {
+                    events.push(StreamEvent::ToolCallDelta {
+                        index,
+                        partial_json,
+                    });
+                }
+                StreamDelta::Other => {}
+            },
+            StreamPayload::MessageDelta { delta, usage } => {
+                if let Some(stop_reason) = delta.stop_reason {
+                    self.stop_reason = Some(stop_reason);
+                }
+                if let Some(usage) = usage {
+                    self.usage.output_tokens = usage.output_tokens;
+                }
+            }
+            StreamPayload::MessageStop => events.push(StreamEvent::Done {
+                stop_reason: self.stop_reason.take(),
+                usage: std::mem::take(&mut self.usage),
+                provider: None,
+                model: self.model.take(),
+            }),
+            StreamPayload::Error { error } => {
+                error!("Anthropic stream error ({}): {}", error.kind, error.message);
+                return Err(ProviderError::from_stream_error(&error.kind, error.message).into());
+            }
+            StreamPayload::Other => {}
+        }
+
+        Ok(events)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamPayload {
+    MessageStart {
+        message: StreamMessage,
+    },
+    ContentBlockStart {
+        index: usize,
+        content_block: StreamContentBlock,
+    },
+    ContentBlockDelta {
+        index: usize,
+        delta: StreamDelta,
+    },
+    MessageDelta {
+        delta: StreamMessageDelta,
+        usage: Option<StreamOutputUsage>,
+    },
+    MessageStop,
+    Error {
+        error: StreamError,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct StreamMessage {
+    model: Option<String>,
+    #[serde(default)]
+    usage: StreamUsage,
+}
+
+/// Usage block of a response or `message_start` event
+#[derive(Deserialize, Default)]
+struct StreamUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+    #[serde(default)]
+    cache_creation_input_tokens: Option<u32>,
+    #[serde(default)]
+    cache_read_input_tokens: Option<u32>,
+}
+
+impl From<StreamUsage> for Usage {
+    fn from(usage: StreamUsage) -> Self {
+        Self {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_input_tokens: usage.cache_creation_input_tokens.unwrap_or(0),
+            cache_read_input_tokens: usage.cache_read_input_tokens.unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct StreamOutputUsage {
+    output_tokens: u32,
 }
 
 #[derive(Deserialize)]
-struct AnthropicContent {
-    text: String,
-}
\ No newline at end of file
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamContentBlock {
+    Text {
+        #[serde(default)]
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamDelta {
+    TextDelta {
+        text: String,
+    },
+    InputJsonDelta {
+        partial_json: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct StreamMessageDelta {
+    stop_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AnthropicErrorResponse {
+    error: StreamError,
+}
+
+/// Error object used both in error responses and in stream `error` events
+#[derive(Deserialize)]
+struct StreamError {
+    #[serde(rename = "type")]
+    kind: String,
+    message: String,
+}
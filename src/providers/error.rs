@@ -0,0 +1,148 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Errors reported by LLM providers.
+///
+/// Providers return these as [`SkynetError::Provider`](crate::SkynetError::Provider).
+#[derive(Debug, Error)]
+pub enum ProviderError {
+    /// The API key is missing, invalid or lacks permission
+    #[error("authentication failed: {message}")]
+    Authentication { message: String },
+
+    /// Too many requests; retry after the given delay if the server sent one
+    #[error("rate limited: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+
+    /// The provider is temporarily overloaded or unavailable
+    #[error("provider overloaded: {message}")]
+    Overloaded { message: String },
+
+    /// The request was malformed or rejected
+    #[error("invalid request: {message}")]
+    InvalidRequest { message: String },
+
+    /// The prompt does not fit in the model's context window
+    #[error("context too long: {message}")]
+    ContextTooLong { message: String },
+
+    /// The request never got a response
+    #[error("network error: {0}")]
+    Network(#[source] reqwest::Error),
+
+    /// The response could not be understood
+    #[error("invalid response: {message}")]
+    InvalidResponse { message: String },
+
+    /// Any other API failure
+    #[error("API error ({status}): {message}")]
+    Api { status: u16, message: String },
+}
+
+impl ProviderError {
+    /// Classify an error response from its status, the provider's error
+    /// `kind` (e.g. `rate_limit_error`) if known, and its message
+    pub fn from_response(
+        status: StatusCode,
+        headers: &HeaderMap,
+        kind: Option<&str>,
+        message: String,
+    ) -> Self {
+        if is_context_overflow(kind, &message) {
+            return Self::ContextTooLong { message };
+        }
+
+        match (kind, status.as_u16()) {
+            (Some("authentication_error" | "permission_error"), _) | (_, 401 | 403) => {
+                Self::Authentication { message }
+            }
+            (Some("rate_limit_error"), _) | (_, 429) => Self::RateLimited {
+                message,
+                retry_after: retry_after(headers),
+            },
+            (Some("overloaded_error"), _) | (_, 503 | 529) => Self::Overloaded { message },
+            (Some("invalid_request_error"), _) | (_, 400 | 404 | 422) => {
+                Self::InvalidRequest { message }
+            }
+            (_, status) => Self::Api { status, message },
+        }
+    }
+
+    /// Classify an error event received in the middle of a stream
+    pub fn from_stream_error(kind: &str, message: String) -> Self {
+        let status = match kind {
+            "overloaded_error" => 529,
+            "rate_limit_error" => 429,
+            "invalid_request_error" => 400,
+            "authentication_error" => 401,
+            _ => 500,
+        };
+        Self::from_response(
+            StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            &HeaderMap::new(),
+            Some(kind),
+            message,
+        )
+    }
+
+    /// Whether the same request may succeed if sent again
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited { .. } | Self::Overloaded { .. } | Self::Network(_) => true,
+            Self::Api { status, .. } => *status == 408 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Wrap a transport error; decoding failures become `InvalidResponse`
+    pub fn from_reqwest(error: reqwest::Error) -> Self {
+        if error.is_decode() {
+            Self::InvalidResponse {
+                message: error.to_string(),
+            }
+        } else {
+            Self::Network(error)
+        }
+    }
+}
+
+impl From<reqwest::Error> for ProviderError {
+    fn from(error: reqwest::Error) -> Self {
+        Self::from_reqwest(error)
+    }
+}
+
+impl From<serde_json::Error> for ProviderError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::InvalidResponse {
+            message: error.to_string(),
+        }
+    }
+}
+
+fn is_context_overflow(kind: Option<&str>, message: &str) -> bool {
+    let message = message.to_lowercase();
+    matches!(kind, Some("request_too_large" | "context_length_exceeded"))
+        || message.contains("prompt is too long")
+        || message.contains("context length")
+        || message.contains("context window")
+        || message.contains("maximum context")
+}
+
+/// Parse a `retry-after` header given in seconds or as an HTTP date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<f64>() {
+        return (seconds >= 0.0).then(|| Duration::from_secs_f64(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
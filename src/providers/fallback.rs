@@ -0,0 +1,541 @@
+use crate::core::message::Message;
+use crate::providers::error::ProviderError;
+use crate::providers::{CompletionRequest, LLMProvider, ResponseStream, StreamEvent};
+use crate::{Result, SkynetError};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Metadata key recording which provider served a response
+pub const PROVIDER_METADATA_KEY: &str = "provider";
+
+/// Fallback chain configuration (`[fallback]` table)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FallbackConfig {
+    /// Names from `[providers]` to try, in order, after the active provider
+    pub providers: Vec<String>,
+
+    /// Consecutive failures that open a provider's circuit
+    pub failure_threshold: u32,
+
+    /// Seconds an open circuit waits before the provider is probed again
+    pub cooldown_secs: u64,
+
+    /// Seconds a passing health check is trusted; a provider is checked again
+    /// before the next request after that, and skipped if unhealthy. 0 turns
+    /// these checks off.
+    pub health_check_interval_secs: u64,
+}
+
+impl Default for FallbackConfig {
+    fn default() -> Self {
+        Self {
+            providers: Vec::new(),
+            failure_threshold: 3,
+            cooldown_secs: 30,
+            health_check_interval_secs: 60,
+        }
+    }
+}
+
+/// Circuit breaker state of one provider in the chain
+#[derive(Debug, Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// When the last health check ran
+    checked_at: Option<Instant>,
+}
+
+/// Whether a provider may receive a request
+enum Admission {
+    /// Circuit closed; send the request
+    Closed,
+    /// Circuit closed but the last health check is stale; check first
+    Stale,
+    /// Cool-down elapsed; probe with `health_check` first
+    Probe,
+    /// Circuit open; skip this provider
+    Open,
+}
+
+/// A provider in the chain and the name it is reported under
+struct Member {
+    name: String,
+    provider: Box<dyn LLMProvider>,
+    circuit: Mutex<Circuit>,
+}
+
+/// Composite provider that tries an ordered list of providers.
+///
+/// Retryable failures move on to the next provider; other errors are
+/// returned immediately. A provider that fails `failure_threshold` times in a
+/// row, or whose health check fails, is skipped for `cooldown_secs`, after
+/// which its `health_check` must pass before it is used again. Health is
+/// checked again before a request once the last check is older than
+/// `health_check_interval_secs`. The serving provider's name is stored in the
+/// response's `metadata["provider"]`.
+///
+/// A model override in the request's options names a model of the first
+/// provider, the active one, so it is dropped for the others.
+pub struct FallbackProvider {
+    members: Vec<Member>,
+    failure_threshold: u32,
+    cooldown: Duration,
+    /// `None` when periodic health checks are off
+    health_check_interval: Option<Duration>,
+}
+
+impl FallbackProvider {
+    /// Create an empty chain using the thresholds from `config`
+    pub fn new(config: &FallbackConfig) -> Self {
+        Self {
+            members: Vec::new(),
+            failure_threshold: config.failure_threshold.max(1),
+            cooldown: Duration::from_secs(config.cooldown_secs),
+            health_check_interval: (config.health_check_interval_secs > 0)
+                .then(|| Duration::from_secs(config.health_check_interval_secs)),
+        }
+    }
+
+    /// Append a provider to the end of the chain
+    pub fn with_provider(
+        mut self,
+        name: impl Into<String>,
+        provider: Box<dyn LLMProvider>,
+    ) -> Self {
+        self.members.push(Member {
+            name: name.into(),
+            provider,
+            circuit: Mutex::new(Circuit::default()),
+        });
+        self
+    }
+
+    /// Names of the providers in the chain, in order
+    pub fn provider_names(&self) -> Vec<&str> {
+        self.members
+            .iter()
+            .map(|member| member.name.as_str())
+            .collect()
+    }
+
+    /// Probe every provider, opening the circuit of those that are unhealthy
+    /// and closing it for those that recovered. Returns the healthy names.
+    pub async fn check_health(&self) -> Vec<&str> {
+        let mut healthy = Vec::new();
+        for member in &self.members {
+            if self.probe(member).await {
+                healthy.push(member.name.as_str());
+            }
+        }
+        healthy
+    }
+
+    /// Run `call` against each admitted provider in turn, with `request` for
+    /// the first provider and `others` for the rest
+    async fn route<'a, T, F, Fut>(
+        &'a self,
+        request: &'a CompletionRequest,
+        others: &'a CompletionRequest,
+        mut call: F,
+    ) -> Result<(T, &'a Member)>
+    where
+        F: FnMut(&'a dyn LLMProvider, &'a CompletionRequest) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut last_error = None;
+
+        for (index, member) in self.members.iter().enumerate() {
+            match self.admission(member) {
+                Admission::Open => {
+                    debug!("Skipping provider {}: circuit open", member.name);
+                    continue;
+                }
+                Admission::Probe | Admission::Stale => {
+                    if !self.probe(member).await {
+                        continue;
+                    }
+                }
+                Admission::Closed => {}
+            }
+
+            let request = if index == 0 { request } else { others };
+            match call(member.provider.as_ref(), request).await {
+                Ok(value) => {
+                    self.record_success(member);
+                    return Ok((value, member));
+                }
+                Err(error) if error.is_retryable() => {
+                    warn!("Provider {} failed, trying next: {}", member.name, error);
+                    self.record_failure(member);
+                    last_error = Some(error);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            SkynetError::Provider(ProviderError::Overloaded {
+                message: format!(
+                    "No provider available: all of {} are unhealthy",
+                    self.provider_names().join(", ")
+                ),
+            })
+        }))
+    }
+
+    fn admission(&self, member: &Member) -> Admission {
+        let circuit = lock(&member.circuit);
+        let stale = |checked_at: Option<Instant>| match (self.health_check_interval, checked_at) {
+            (Some(interval), Some(at)) => at.elapsed() >= interval,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        match circuit.open_until {
+            None if stale(circuit.checked_at) => Admission::Stale,
+            None => Admission::Closed,
+            Some(until) if Instant::now() >= until => Admission::Probe,
+            Some(_) => Admission::Open,
+        }
+    }
+
+    /// Run the provider's health check and update its circuit accordingly
+    async fn probe(&self, member: &Member) -> bool {
+        let healthy = match member.provider.health_check().await {
+            Ok(healthy) => healthy,
+            Err(e) => {
+                debug!("Health check of {} failed: {}", member.name, e);
+                false
+            }
+        };
+
+        lock(&member.circuit).checked_at = Some(Instant::now());
+        if healthy {
+            self.half_open(member);
+        } else {
+            warn!(
+                "Provider {} is unhealthy, skipping it for {:?}",
+                member.name, self.cooldown
+            );
+            self.open(member);
+        }
+        healthy
+    }
+
+    fn record_success(&self, member: &Member) {
+        let mut circuit = lock(&member.circuit);
+        if circuit.open_until.is_some() {
+            info!("Provider {} recovered, closing circuit", member.name);
+        }
+        *circuit = Circuit {
+            checked_at: circuit.checked_at,
+            ..Circuit::default()
+        };
+    }
+
+    fn record_failure(&self, member: &Member) {
+        let mut circuit = lock(&member.circuit);
+        circuit.consecutive_failures += 1;
+        if circuit.consecutive_failures >= self.failure_threshold {
+            warn!(
+                "Opening circuit for provider {} for {:?} after {} consecutive failures",
+                member.name, self.cooldown, circuit.consecutive_failures
+            );
+            circuit.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+
+    /// Let traffic through an open circuit after a passing health check; the
+    /// next failure re-opens it straight away
+    fn half_open(&self, member: &Member) {
+        let mut circuit = lock(&member.circuit);
+        if circuit.open_until.take().is_some() {
+            debug!(
+                "Provider {} passed its health check, half-opening circuit",
+                member.name
+            );
+            circuit.consecutive_failures = self.failure_threshold - 1;
+        }
+    }
+
+    fn open(&self, member: &Member) {
+        lock(&member.circuit).open_until = Some(Instant::now() + self.cooldown);
+    }
+
+    fn first(&self) -> Option<&Member> {
+        self.members.first()
+    }
+}
+
+/// `request` without its model override, for providers other than the one
+/// the override was meant for
+fn without_model(request: &CompletionRequest) -> Cow<'_, CompletionRequest> {
+    if request.options.model.is_none() {
+        return Cow::Borrowed(request);
+    }
+    let mut request = request.clone();
+    request.options.model = None;
+    Cow::Owned(request)
+}
+
+fn lock(circuit: &Mutex<Circuit>) -> std::sync::MutexGuard<'_, Circuit> {
+    circuit
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[async_trait]
+impl LLMProvider for FallbackProvider {
+    async fn complete(&self, request: &CompletionRequest) -> Result<Message> {
+        let others = without_model(request);
+        let (message, member) = self
+            .route(request, &others, |provider, request| {
+                provider.complete(request)
+            })
+            .await?;
+        Ok(message.with_metadata(
+            PROVIDER_METADATA_KEY.to_string(),
+            member.name.clone().into(),
+        ))
+    }
+
+    async fn generate_stream(&self, request: &CompletionRequest) -> Result<ResponseStream> {
+        let others = without_model(request);
+        let (stream, member) = self
+            .route(request, &others, |provider, request| {
+                provider.generate_stream(request)
+            })
+            .await?;
+        debug!("Streaming from provider {}", member.name);
+        let name = member.name.clone();
+        Ok(Box::pin(stream.map(move |event| match event {
+            Ok(StreamEvent::Done {
+                stop_reason,
+                usage,
+                provider: None,
+                model,
+            }) => Ok(StreamEvent::Done {
+                stop_reason,
+                usage,
+                provider: Some(name.clone()),
+                model,
+            }),
+            other => other,
+        })))
+    }
+
+    fn name(&self) -> &str {
+        "fallback"
+    }
+
+    fn model(&self) -> &str {
+        self.first().map_or("", |member| member.provider.model())
+    }
+
+    /// Healthy if any provider in the chain is
+    async fn health_check(&self) -> Result<bool> {
+        Ok(!self.check_health().await.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::scripted::{overloaded, ScriptedProvider};
+    use crate::providers::StreamAccumulator;
+
+    /// Chain without periodic health checks
+    fn chain(
+        threshold: u32,
+        cooldown_secs: u64,
+        members: &[&ScriptedProvider],
+    ) -> FallbackProvider {
+        let config = FallbackConfig {
+            providers: Vec::new(),
+            failure_threshold: threshold,
+            cooldown_secs,
+            health_check_interval_secs: 0,
+        };
+        chain_with(&config, members)
+    }
+
+    fn chain_with(config: &FallbackConfig, members: &[&ScriptedProvider]) -> FallbackProvider {
+        members
+            .iter()
+            .fold(FallbackProvider::new(config), |chain, member| {
+                chain.with_provider(member.name(), member.boxed())
+            })
+    }
+
+    async fn complete(chain: &FallbackProvider) -> Result<Message> {
+        chain.complete(&CompletionRequest::default()).await
+    }
+
+    fn is_open(chain: &FallbackProvider, index: usize) -> bool {
+        lock(&chain.members[index].circuit).open_until.is_some()
+    }
+
+    /// Make the last health check of the provider at `index` stale
+    fn age_health_check(chain: &FallbackProvider, index: usize) {
+        lock(&chain.members[index].circuit).checked_at =
+            Some(Instant::now() - Duration::from_secs(3600));
+    }
+
+    #[tokio::test]
+    async fn falls_back_on_retryable_errors_and_names_the_server() {
+        let primary = ScriptedProvider::new("primary");
+        let secondary = ScriptedProvider::new("secondary");
+        primary.fail(overloaded());
+        let chain = chain(3, 30, &[&primary, &secondary]);
+
+        let reply = complete(&chain).await.unwrap();
+        assert_eq!(reply.content, "ok from secondary");
+        assert_eq!(reply.metadata[PROVIDER_METADATA_KEY], "secondary");
+        assert_eq!((primary.calls(), secondary.calls()), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn names_the_server_of_a_stream() {
+        let primary = ScriptedProvider::new("primary");
+        let secondary = ScriptedProvider::new("secondary");
+        primary.fail(overloaded());
+        let chain = chain(3, 30, &[&primary, &secondary]);
+
+        let mut stream = chain
+            .generate_stream(&CompletionRequest::default())
+            .await
+            .unwrap();
+        let mut accumulator = StreamAccumulator::new();
+        while let Some(event) = stream.next().await {
+            accumulator.push(event.unwrap());
+        }
+        let reply = accumulator.into_message("scripted-model").unwrap();
+        assert_eq!(reply.content, "ok from secondary");
+        assert_eq!(reply.metadata[PROVIDER_METADATA_KEY], "secondary");
+    }
+
+    #[tokio::test]
+    async fn returns_permanent_errors_without_falling_back() {
+        let primary = ScriptedProvider::new("primary");
+        let secondary = ScriptedProvider::new("secondary");
+        primary.fail(ProviderError::Authentication {
+            message: "bad key".to_string(),
+        });
+        let chain = chain(3, 30, &[&primary, &secondary]);
+
+        assert!(complete(&chain).await.is_err());
+        assert_eq!(secondary.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn skips_a_provider_while_its_circuit_is_open() {
+        let primary = ScriptedProvider::new("primary");
+        let secondary = ScriptedProvider::new("secondary");
+        primary.fail(overloaded()).fail(overloaded());
+        let chain = chain(2, 60, &[&primary, &secondary]);
+
+        complete(&chain).await.unwrap();
+        assert!(!is_open(&chain, 0));
+        complete(&chain).await.unwrap();
+        assert!(is_open(&chain, 0));
+
+        for _ in 0..3 {
+            let reply = complete(&chain).await.unwrap();
+            assert_eq!(reply.metadata[PROVIDER_METADATA_KEY], "secondary");
+        }
+        assert_eq!(primary.calls(), 2);
+        assert_eq!(primary.health_checks(), 0);
+        assert_eq!(secondary.calls(), 5);
+    }
+
+    #[tokio::test]
+    async fn probes_after_the_cooldown_before_closing_again() {
+        let primary = ScriptedProvider::new("primary");
+        let secondary = ScriptedProvider::new("secondary");
+        primary.fail(overloaded()).fail(overloaded());
+        // No cooldown, so every request after opening probes the provider
+        let chain = chain(2, 0, &[&primary, &secondary]);
+        complete(&chain).await.unwrap();
+        complete(&chain).await.unwrap();
+        assert!(is_open(&chain, 0));
+
+        // A failing probe keeps the provider out
+        primary.set_healthy(false);
+        complete(&chain).await.unwrap();
+        assert_eq!((primary.health_checks(), primary.calls()), (1, 2));
+        assert!(is_open(&chain, 0));
+
+        // A passing probe half-opens the circuit; one failure re-opens it
+        primary.set_healthy(true);
+        primary.fail(overloaded());
+        complete(&chain).await.unwrap();
+        assert_eq!((primary.health_checks(), primary.calls()), (2, 3));
+        assert!(is_open(&chain, 0));
+
+        // A success after the next probe closes it
+        let reply = complete(&chain).await.unwrap();
+        assert_eq!(reply.metadata[PROVIDER_METADATA_KEY], "primary");
+        assert_eq!((primary.health_checks(), primary.calls()), (3, 4));
+        assert!(!is_open(&chain, 0));
+        assert_eq!(lock(&chain.members[0].circuit).consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn skips_a_provider_that_reports_unhealthy() {
+        let primary = ScriptedProvider::new("primary");
+        let secondary = ScriptedProvider::new("secondary");
+        primary.set_healthy(false);
+        let chain = chain_with(&FallbackConfig::default(), &[&primary, &secondary]);
+
+        for _ in 0..3 {
+            let reply = complete(&chain).await.unwrap();
+            assert_eq!(reply.metadata[PROVIDER_METADATA_KEY], "secondary");
+        }
+        assert_eq!((primary.health_checks(), primary.calls()), (1, 0));
+        assert!(is_open(&chain, 0));
+    }
+
+    #[tokio::test]
+    async fn checks_health_again_once_the_last_check_is_stale() {
+        let primary = ScriptedProvider::new("primary");
+        let secondary = ScriptedProvider::new("secondary");
+        let chain = chain_with(&FallbackConfig::default(), &[&primary, &secondary]);
+
+        for _ in 0..3 {
+            complete(&chain).await.unwrap();
+        }
+        assert_eq!((primary.health_checks(), primary.calls()), (1, 3));
+
+        age_health_check(&chain, 0);
+        primary.set_healthy(false);
+        let reply = complete(&chain).await.unwrap();
+        assert_eq!(reply.metadata[PROVIDER_METADATA_KEY], "secondary");
+        assert_eq!((primary.health_checks(), primary.calls()), (2, 3));
+        assert_eq!(secondary.health_checks(), 1);
+    }
+
+    #[tokio::test]
+    async fn sends_the_model_override_to_the_first_provider_only() {
+        let primary = ScriptedProvider::new("primary");
+        let secondary = ScriptedProvider::new("secondary");
+        primary.fail(overloaded());
+        let chain = chain(3, 30, &[&primary, &secondary]);
+
+        let mut request = CompletionRequest::default();
+        request.options.model = Some("primary-large".to_string());
+        request.options.max_tokens = Some(100);
+        chain.complete(&request).await.unwrap();
+
+        assert_eq!(primary.requests()[0].options, request.options);
+        let fallback = &secondary.requests()[0].options;
+        assert_eq!(fallback.model, None);
+        assert_eq!(fallback.max_tokens, Some(100));
+    }
+}
@@ -1,6 +1,22 @@
 //! LLM Provider implementations
 
 pub mod anthropic;
+pub mod error;
+pub mod fallback;
+pub mod ollama;
+pub mod openai;
+pub mod registry;
+pub mod retry;
+#[cfg(test)]
+pub(crate) mod scripted;
+mod sse;
 pub mod r#trait;
 
-pub use r#trait::LLMProvider;
\ No newline at end of file
+pub use error::ProviderError;
+pub use fallback::{FallbackConfig, FallbackProvider};
+pub use r#trait::{
+    CompletionRequest, Generation, GenerationOptions, LLMProvider, ProviderConfig, ResponseStream,
+    StreamAccumulator, StreamEvent, ToolDefinition, Usage,
+};
+pub use registry::{ProviderFactory, ProviderRegistry};
+pub use retry::{ErrorClass, RetryConfig, RetryProvider};
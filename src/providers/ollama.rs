@@ -0,0 +1,457 @@
+use crate::core::message::{Message, Role, ToolCall};
+use crate::providers::error::ProviderError;
+use crate::providers::{
+    CompletionRequest, GenerationOptions, LLMProvider, ResponseStream, StreamEvent, ToolDefinition,
+    Usage,
+};
+use crate::{Result, SkynetError};
+use async_trait::async_trait;
+use futures_util::stream::{self, StreamExt};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tracing::{debug, error, info};
+
+/// Default address of a local Ollama server
+pub const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+/// Provider for a local Ollama server using its native `/api/chat` endpoint
+pub struct OllamaProvider {
+    client: Client,
+    model: String,
+    base_url: String,
+    defaults: GenerationOptions,
+}
+
+/// A model installed on the Ollama server
+#[derive(Debug, Clone, Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub digest: String,
+    pub modified_at: Option<String>,
+}
+
+impl OllamaProvider {
+    /// Create a provider for `model` on the default local server
+    pub fn new(model: String) -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            model,
+            base_url: DEFAULT_OLLAMA_BASE_URL.to_string(),
+            defaults: GenerationOptions::default(),
+        })
+    }
+
+    /// Set the default generation options; a `model` here replaces the
+    /// provider's model
+    pub fn with_options(mut self, options: GenerationOptions) -> Self {
+        if let Some(model) = &options.model {
+            self.model = model.clone();
+        }
+        self.defaults = options;
+        self
+    }
+
+    /// Use a different Ollama server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// List the models installed on the server
+    pub async fn list_models(&self) -> Result<Vec<OllamaModel>> {
+        let response = self
+            .client
+            .get(format!("{}/api/tags", self.base_url))
+            .send()
+            .await
+            .map_err(ProviderError::from)?;
+
+        if !response.status().is_success() {
+            return Err(error_from_response(response).await.into());
+        }
+
+        let tags: OllamaTags = response.json().await.map_err(ProviderError::from)?;
+        Ok(tags.models)
+    }
+
+    /// Download `model` to the server, waiting until the pull completes
+    pub async fn pull_model(&self, model: &str) -> Result<()> {
+        info!("Pulling Ollama model {}", model);
+        let response = self
+            .client
+            .post(format!("{}/api/pull", self.base_url))
+            .json(&serde_json::json!({ "model": model, "stream": false }))
+            .send()
+            .await
+            .map_err(ProviderError::from)?;
+
+        if !response.status().is_success() {
+            let error = error_from_response(response).await;
+            error!("Ollama pull of {} failed: {}", model, error);
+            return Err(error.into());
+        }
+
+        Ok(())
+    }
+
+    /// Convert internal messages to Ollama chat format
+    fn convert_messages(&self, messages: &[Message]) -> Vec<OllamaMessage> {
+        messages
+            .iter()
+            .map(|msg| OllamaMessage {
+                role: match msg.role {
+                    Role::System => "system",
+                    Role::User => "user",
+                    Role::Assistant => "assistant",
+                    Role::Tool => "tool",
+                }
+                .to_string(),
+                content: msg.content.clone(),
+                tool_calls: msg
+                    .tool_calls()
+                    .into_iter()
+                    .map(|call| OllamaToolCall {
+                        function: OllamaFunctionCall {
+                            name: call.name,
+                            arguments: call.arguments,
+                        },
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Build a request body for `/api/chat`
+    fn build_request(&self, request: &CompletionRequest, stream: bool) -> OllamaRequest {
+        let options = request.options.with_defaults(&self.defaults);
+
+        OllamaRequest {
+            model: options.model.unwrap_or_else(|| self.model.clone()),
+            messages: self.convert_messages(&request.messages),
+            tools: request.tools.iter().map(OllamaTool::from).collect(),
+            options: OllamaOptions {
+                num_predict: options.max_tokens,
+                temperature: options.temperature,
+                top_p: options.top_p,
+                stop: options.stop_sequences,
+            },
+            stream,
+        }
+    }
+
+    /// Send a chat request and return the raw response, failing on non-2xx status
+    async fn send(&self, request: &OllamaRequest) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(request)
+            .send()
+            .await
+            .map_err(ProviderError::from)?;
+
+        if !response.status().is_success() {
+            let error = error_from_response(response).await;
+            error!("Ollama API error: {}", error);
+            return Err(error.into());
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for OllamaProvider {
+    async fn complete(&self, request: &CompletionRequest) -> Result<Message> {
+        debug!("Generating response with Ollama model {}", self.model);
+
+        let body = self.build_request(request, false);
+        let response = self.send(&body).await?;
+        let chunk: OllamaChunk = response.json().await.map_err(ProviderError::from)?;
+
+        let model = chunk.model.clone().unwrap_or_else(|| body.model.clone());
+        let usage = chunk.usage();
+        let message = chunk.message.unwrap_or_default();
+        let calls: Vec<ToolCall> = message.tool_calls.into_iter().map(ToolCall::from).collect();
+
+        let message = if calls.is_empty() {
+            Message::assistant(message.content)
+        } else {
+            Message::tool_call(message.content, calls)
+        };
+        Ok(message.with_usage(&model, &usage))
+    }
+
+    async fn generate_stream(&self, request: &CompletionRequest) -> Result<ResponseStream> {
+        debug!("Streaming response with Ollama model {}", self.model);
+
+        let body = self.build_request(request, true);
+        let response = self.send(&body).await?;
+
+        let mut next_tool_index = 0;
+        let bytes = response
+            .bytes_stream()
+            .map(|chunk| chunk.map_err(ProviderError::from));
+        let events = ndjson_stream(bytes)
+            .map(move |line| {
+                line.and_then(|line| {
+                    let chunk: OllamaChunk =
+                        serde_json::from_str(&line).map_err(ProviderError::from)?;
+                    chunk.into_events(&mut next_tool_index)
+                })
+            })
+            .flat_map(|result| {
+                let items: Vec<Result<StreamEvent>> = match result {
+                    Ok(events) => events.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(items)
+            });
+
+        Ok(Box::pin(events))
+    }
+
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Healthy only if the server answers and has the configured model installed
+    async fn health_check(&self) -> Result<bool> {
+        let models = self.list_models().await?;
+        let installed = models.iter().any(|model| {
+            model.name == self.model || model.name == format!("{}:latest", self.model)
+        });
+
+        if !installed {
+            debug!("Ollama model {} is not installed", self.model);
+        }
+        Ok(installed)
+    }
+}
+
+/// Classify a non-2xx response; Ollama reports errors as `{"error": "..."}`
+async fn error_from_response(response: reqwest::Response) -> ProviderError {
+    let status = response.status();
+    let headers = response.headers().clone();
+    let error_text = match response.text().await {
+        Ok(text) => text,
+        Err(e) => return ProviderError::from(e),
+    };
+
+    let message = serde_json::from_str::<serde_json::Value>(&error_text)
+        .ok()
+        .and_then(|body| body.get("error")?.as_str().map(str::to_string))
+        .unwrap_or(error_text);
+    ProviderError::from_response(status, &headers, None, message)
+}
+
+/// Split a byte stream into non-empty newline-delimited lines
+fn ndjson_stream<S, B, E>(bytes: S) -> impl futures_util::Stream<Item = Result<String>> + Send
+where
+    S: futures_util::Stream<Item = std::result::Result<B, E>> + Send + Unpin + 'static,
+    B: AsRef<[u8]>,
+    E: Into<SkynetError> + Send + 'static,
+{
+    let state = (bytes, Vec::new(), VecDeque::new(), false);
+
+    stream::unfold(
+        state,
+        |(mut bytes, mut buffer, mut pending, mut finished)| async move {
+            loop {
+                if let Some(line) = pending.pop_front() {
+                    return Some((Ok(line), (bytes, buffer, pending, finished)));
+                }
+                if finished {
+                    return None;
+                }
+
+                match bytes.next().await {
+                    Some(Ok(chunk)) => {
+                        buffer.extend_from_slice(chunk.as_ref());
+                        while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+                            let line: Vec<u8> = buffer.drain(..=pos).collect();
+                            let line = String::from_utf8_lossy(&line).trim().to_string();
+                            if !line.is_empty() {
+                                pending.push_back(line);
+                            }
+                        }
+                    }
+                    Some(Err(e)) => {
+                        finished = true;
+                        return Some((Err(e.into()), (bytes, buffer, pending, finished)));
+                    }
+                    None => {
+                        finished = true;
+                        let line = String::from_utf8_lossy(&buffer).trim().to_string();
+                        if !line.is_empty() {
+                            pending.push_back(line);
+                        }
+                    }
+                }
+            }
+        },
+    )
+}
+
+#[derive(Serialize)]
+struct OllamaRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<OllamaTool>,
+    options: OllamaOptions,
+    stream: bool,
+}
+
+/// Ollama's model parameters; `num_predict` is its name for `max_tokens`
+#[derive(Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct OllamaMessage {
+    #[serde(default)]
+    role: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<OllamaToolCall>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OllamaFunctionCall {
+    name: String,
+    arguments: serde_json::Value,
+}
+
+impl From<OllamaToolCall> for ToolCall {
+    /// Ollama does not assign IDs to tool calls, so generate one
+    fn from(call: OllamaToolCall) -> Self {
+        Self {
+            id: format!("call_{}", uuid::Uuid::new_v4().simple()),
+            name: call.function.name,
+            arguments: call.function.arguments,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct OllamaTool {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    function: OllamaFunction,
+}
+
+#[derive(Serialize)]
+struct OllamaFunction {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+impl From<&ToolDefinition> for OllamaTool {
+    fn from(tool: &ToolDefinition) -> Self {
+        Self {
+            kind: "function",
+            function: OllamaFunction {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                parameters: tool.input_schema.clone(),
+            },
+        }
+    }
+}
+
+/// A `/api/chat` response, or one line of a streamed response
+#[derive(Deserialize)]
+struct OllamaChunk {
+    model: Option<String>,
+    message: Option<OllamaMessage>,
+    #[serde(default)]
+    done: bool,
+    done_reason: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: u32,
+    #[serde(default)]
+    eval_count: u32,
+    error: Option<String>,
+}
+
+impl OllamaChunk {
+    fn usage(&self) -> Usage {
+        Usage {
+            input_tokens: self.prompt_eval_count,
+            output_tokens: self.eval_count,
+            ..Usage::default()
+        }
+    }
+
+    /// Translate a streamed chunk into stream events
+    fn into_events(self, next_tool_index: &mut usize) -> Result<Vec<StreamEvent>> {
+        if let Some(error) = self.error {
+            error!("Ollama stream error: {}", error);
+            return Err(ProviderError::from_stream_error("api_error", error).into());
+        }
+
+        let usage = self.usage();
+        let mut events = Vec::new();
+        if let Some(message) = self.message {
+            if !message.content.is_empty() {
+                events.push(StreamEvent::TextDelta(message.content));
+            }
+            // Ollama sends each tool call whole, never as fragments
+            for call in message.tool_calls {
+                let index = *next_tool_index;
+                *next_tool_index += 1;
+                let arguments = call.function.arguments.to_string();
+                let call = ToolCall::from(call);
+                events.push(StreamEvent::ToolCallStart {
+                    index,
+                    id: call.id,
+                    name: call.name,
+                });
+                events.push(StreamEvent::ToolCallDelta {
+                    index,
+                    partial_json: arguments,
+                });
+            }
+        }
+
+        if self.done {
+            events.push(StreamEvent::Done {
+                usage,
+                stop_reason: self.done_reason,
+                provider: None,
+                model: self.model,
+            });
+        }
+
+        Ok(events)
+    }
+}
+
+#[derive(Deserialize)]
+struct OllamaTags {
+    #[serde(default)]
+    models: Vec<OllamaModel>,
+}
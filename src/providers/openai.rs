@@ -0,0 +1,523 @@
+use crate::config::Secret;
+use crate::core::message::{Message, Role, ToolCall};
+use crate::providers::error::ProviderError;
+use crate::providers::sse::{self, SseEvent};
+use crate::providers::{
+    CompletionRequest, GenerationOptions, LLMProvider, ResponseStream, StreamEvent, ToolDefinition,
+    Usage,
+};
+use crate::Result;
+use async_trait::async_trait;
+use futures_util::stream::{self, StreamExt};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::{debug, error};
+
+/// Default endpoint for the hosted OpenAI API
+pub const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// OpenAI-compatible chat completions provider.
+///
+/// Works with any server speaking the `/v1/chat/completions` wire format,
+/// such as vLLM, llama.cpp server or LM Studio, by pointing `base_url` at it.
+pub struct OpenAIProvider {
+    client: Client,
+    api_key: Secret,
+    model: String,
+    base_url: String,
+    defaults: GenerationOptions,
+}
+
+impl OpenAIProvider {
+    /// Create a new provider for the hosted OpenAI API
+    pub fn new(api_key: impl Into<Secret>, model: String) -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            api_key: api_key.into(),
+            model,
+            base_url: DEFAULT_OPENAI_BASE_URL.to_string(),
+            defaults: GenerationOptions::default(),
+        })
+    }
+
+    /// Set the default generation options; a `model` here replaces the
+    /// provider's model
+    pub fn with_options(mut self, options: GenerationOptions) -> Self {
+        if let Some(model) = &options.model {
+            self.model = model.clone();
+        }
+        self.defaults = options;
+        self
+    }
+
+    /// Use a different OpenAI-compatible server, e.g. `http://localhost:8000/v1`
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Convert internal messages to chat completions format
+    fn convert_messages(&self, messages: &[Message]) -> Vec<OpenAIMessage> {
+        messages
+            .iter()
+            .map(|msg| match msg.role {
+                Role::System => OpenAIMessage::text("system", &msg.content),
+                Role::User => OpenAIMessage::text("user", &msg.content),
+                Role::Assistant => {
+                    let calls: Vec<OpenAIToolCall> = msg
+                        .tool_calls()
+                        .into_iter()
+                        .map(OpenAIToolCall::from)
+                        .collect();
+                    if calls.is_empty() {
+                        OpenAIMessage::text("assistant", &msg.content)
+                    } else {
+                        OpenAIMessage {
+                            role: "assistant".to_string(),
+                            content: (!msg.content.is_empty()).then(|| msg.content.clone()),
+                            tool_calls: Some(calls),
+                            tool_call_id: None,
+                        }
+                    }
+                }
+                Role::Tool => OpenAIMessage {
+                    role: "tool".to_string(),
+                    content: Some(msg.content.clone()),
+                    tool_calls: None,
+                    tool_call_id: msg.tool_call_id().map(str::to_string),
+                },
+            })
+            .collect()
+    }
+
+    /// Build a request body for the chat completions endpoint
+    fn build_request(&self, request: &CompletionRequest, stream: bool) -> OpenAIRequest {
+        let options = request.options.with_defaults(&self.defaults);
+
+        OpenAIRequest {
+            model: options.model.unwrap_or_else(|| self.model.clone()),
+            messages: self.convert_messages(&request.messages),
+            tools: request.tools.iter().map(OpenAITool::from).collect(),
+            max_tokens: options.max_tokens,
+            temperature: options.temperature,
+            top_p: options.top_p,
+            stop: options.stop_sequences,
+            metadata: options.metadata,
+            stream,
+            stream_options: stream.then_some(StreamOptions {
+                include_usage: true,
+            }),
+        }
+    }
+
+    /// Attach the bearer token, if one is configured
+    fn authorize(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if self.api_key.is_empty() {
+            builder
+        } else {
+            builder.bearer_auth(self.api_key.expose())
+        }
+    }
+
+    /// Send a request and return the raw response, failing on non-2xx status
+    async fn send(&self, request: &OpenAIRequest) -> Result<reqwest::Response> {
+        let response = self
+            .authorize(
+                self.client
+                    .post(format!("{}/chat/completions", self.base_url)),
+            )
+            .json(request)
+            .send()
+            .await
+            .map_err(ProviderError::from)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let error_text = response.text().await.map_err(ProviderError::from)?;
+            error!("OpenAI API error ({}): {}", status, error_text);
+
+            let error = match serde_json::from_str::<OpenAIErrorResponse>(&error_text) {
+                Ok(body) => {
+                    let kind = body.error.code.or(body.error.kind);
+                    ProviderError::from_response(
+                        status,
+                        &headers,
+                        kind.as_deref(),
+                        body.error.message,
+                    )
+                }
+                Err(_) => ProviderError::from_response(status, &headers, None, error_text),
+            };
+            return Err(error.into());
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for OpenAIProvider {
+    async fn complete(&self, request: &CompletionRequest) -> Result<Message> {
+        debug!(
+            "Generating response with OpenAI-compatible API at {}",
+            self.base_url
+        );
+
+        let body = self.build_request(request, false);
+        let response = self.send(&body).await?;
+        let completion: OpenAIResponse = response.json().await.map_err(ProviderError::from)?;
+
+        let model = completion.model.unwrap_or_else(|| body.model.clone());
+        let usage = completion.usage.map(Usage::from).unwrap_or_default();
+
+        let choice = completion.choices.into_iter().next().ok_or_else(|| {
+            ProviderError::InvalidResponse {
+                message: "No choices in OpenAI response".to_string(),
+            }
+        })?;
+
+        let text = choice.message.content.unwrap_or_default();
+        let calls: Vec<ToolCall> = choice
+            .message
+            .tool_calls
+            .unwrap_or_default()
+            .into_iter()
+            .map(ToolCall::from)
+            .collect();
+
+        let message = if calls.is_empty() {
+            Message::assistant(text)
+        } else {
+            Message::tool_call(text, calls)
+        };
+        Ok(message.with_usage(&model, &usage))
+    }
+
+    async fn generate_stream(&self, request: &CompletionRequest) -> Result<ResponseStream> {
+        debug!(
+            "Streaming response with OpenAI-compatible API at {}",
+            self.base_url
+        );
+
+        let body = self.build_request(request, true);
+        let response = self.send(&body).await?;
+
+        let mut state = StreamState::default();
+        let bytes = response
+            .bytes_stream()
+            .map(|chunk| chunk.map_err(ProviderError::from));
+        let events = sse::event_stream(bytes)
+            .map(move |event| event.and_then(|event| state.handle(&event)))
+            .flat_map(|result| {
+                let items: Vec<Result<StreamEvent>> = match result {
+                    Ok(events) => events.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(items)
+            });
+
+        Ok(Box::pin(events))
+    }
+
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        let response = self
+            .authorize(self.client.get(format!("{}/models", self.base_url)))
+            .send()
+            .await
+            .map_err(ProviderError::from)?;
+        Ok(response.status().is_success())
+    }
+}
+
+#[derive(Serialize)]
+struct OpenAIRequest {
+    model: String,
+    messages: Vec<OpenAIMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<OpenAITool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+#[derive(Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+#[derive(Serialize)]
+struct OpenAIMessage {
+    role: String,
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<OpenAIToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+impl OpenAIMessage {
+    fn text(role: &str, content: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            content: Some(content.to_string()),
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct OpenAITool {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    function: OpenAIFunction,
+}
+
+#[derive(Serialize)]
+struct OpenAIFunction {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+impl From<&ToolDefinition> for OpenAITool {
+    fn from(tool: &ToolDefinition) -> Self {
+        Self {
+            kind: "function",
+            function: OpenAIFunction {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                parameters: tool.input_schema.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpenAIToolCall {
+    id: String,
+    #[serde(rename = "type", default = "function_kind")]
+    kind: String,
+    function: OpenAIFunctionCall,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpenAIFunctionCall {
+    name: String,
+    /// JSON-encoded arguments, as the wire format sends them
+    arguments: String,
+}
+
+fn function_kind() -> String {
+    "function".to_string()
+}
+
+impl From<ToolCall> for OpenAIToolCall {
+    fn from(call: ToolCall) -> Self {
+        Self {
+            id: call.id,
+            kind: function_kind(),
+            function: OpenAIFunctionCall {
+                name: call.name,
+                arguments: call.arguments.to_string(),
+            },
+        }
+    }
+}
+
+impl From<OpenAIToolCall> for ToolCall {
+    fn from(call: OpenAIToolCall) -> Self {
+        // Models occasionally emit invalid JSON; keep the raw text so schema
+        // validation can report it back instead of dropping the call.
+        let arguments = serde_json::from_str(&call.function.arguments)
+            .unwrap_or(serde_json::Value::String(call.function.arguments));
+        Self {
+            id: call.id,
+            name: call.function.name,
+            arguments,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenAIResponse {
+    choices: Vec<OpenAIChoice>,
+    model: Option<String>,
+    usage: Option<StreamUsage>,
+}
+
+#[derive(Deserialize)]
+struct OpenAIChoice {
+    message: OpenAIResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct OpenAIResponseMessage {
+    content: Option<String>,
+    tool_calls: Option<Vec<OpenAIToolCall>>,
+}
+
+/// Accumulates state across chat completion chunks
+#[derive(Default)]
+struct StreamState {
+    usage: Usage,
+    stop_reason: Option<String>,
+    model: Option<String>,
+}
+
+impl StreamState {
+    /// Translate one server-sent event into zero or more stream events
+    fn handle(&mut self, event: &SseEvent) -> Result<Vec<StreamEvent>> {
+        let data = event.data.trim();
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+        if data == "[DONE]" {
+            return Ok(vec![StreamEvent::Done {
+                stop_reason: self.stop_reason.take(),
+                usage: std::mem::take(&mut self.usage),
+                provider: None,
+                model: self.model.take(),
+            }]);
+        }
+
+        let chunk: StreamChunk = serde_json::from_str(data).map_err(ProviderError::from)?;
+        if let Some(error) = chunk.error {
+            error!("OpenAI stream error: {}", error.message);
+            let kind = error.code.or(error.kind).unwrap_or_default();
+            return Err(ProviderError::from_stream_error(&kind, error.message).into());
+        }
+        if let Some(usage) = chunk.usage {
+            self.usage = usage.into();
+        }
+        if chunk.model.is_some() {
+            self.model = chunk.model;
+        }
+
+        let mut events = Vec::new();
+        for choice in chunk.choices {
+            if let Some(reason) = choice.finish_reason {
+                self.stop_reason = Some(reason);
+            }
+            if let Some(text) = choice.delta.content.filter(|text| !text.is_empty()) {
+                events.push(StreamEvent::TextDelta(text));
+            }
+            for call in choice.delta.tool_calls.unwrap_or_default() {
+                let function = call.function.unwrap_or_default();
+                if let (Some(id), Some(name)) = (call.id, function.name) {
+                    events.push(StreamEvent::ToolCallStart {
+                        index: call.index,
+                        id,
+                        name,
+                    });
+                }
+                if let Some(arguments) = function.arguments.filter(|args| !args.is_empty()) {
+                    events.push(StreamEvent::ToolCallDelta {
+                        index: call.index,
+                        partial_json: arguments,
+                    });
+                }
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+#[derive(Deserialize)]
+struct StreamChunk {
+    model: Option<String>,
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    usage: Option<StreamUsage>,
+    error: Option<StreamError>,
+}
+
+#[derive(Deserialize)]
+struct StreamChoice {
+    delta: StreamDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StreamDelta {
+    content: Option<String>,
+    tool_calls: Option<Vec<StreamToolCall>>,
+}
+
+#[derive(Deserialize)]
+struct StreamToolCall {
+    index: usize,
+    id: Option<String>,
+    function: Option<StreamFunction>,
+}
+
+#[derive(Deserialize, Default)]
+struct StreamFunction {
+    name: Option<String>,
+    arguments: Option<String>,
+}
+
+/// Usage block of a response or final stream chunk
+#[derive(Deserialize)]
+struct StreamUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    prompt_tokens_details: Option<PromptTokensDetails>,
+}
+
+#[derive(Deserialize)]
+struct PromptTokensDetails {
+    #[serde(default)]
+    cached_tokens: u32,
+}
+
+impl From<StreamUsage> for Usage {
+    /// `prompt_tokens` includes cached tokens; split them out
+    fn from(usage: StreamUsage) -> Self {
+        let cached = usage
+            .prompt_tokens_details
+            .map_or(0, |details| details.cached_tokens);
+        Self {
+            input_tokens: usage.prompt_tokens.saturating_sub(cached),
+            output_tokens: usage.completion_tokens,
+            cache_read_input_tokens: cached,
+            ..Usage::default()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenAIErrorResponse {
+    error: StreamError,
+}
+
+/// Error object used both in error responses and in streamed chunks
+#[derive(Deserialize)]
+struct StreamError {
+    message: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    code: Option<String>,
+}
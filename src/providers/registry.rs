@@ -0,0 +1,146 @@
+use crate::config::Config;
+use crate::providers::anthropic::AnthropicProvider;
+use crate::providers::fallback::FallbackProvider;
+use crate::providers::ollama::OllamaProvider;
+use crate::providers::openai::OpenAIProvider;
+use crate::providers::r#trait::ProviderConfig;
+use crate::providers::retry::RetryProvider;
+use crate::providers::LLMProvider;
+use crate::{Result, SkynetError};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+
+/// Constructor turning a provider configuration into a provider instance
+pub type ProviderFactory =
+    Arc<dyn Fn(&ProviderConfig) -> Result<Box<dyn LLMProvider>> + Send + Sync>;
+
+/// Maps provider `type` strings to constructors.
+///
+/// `ProviderRegistry::default()` knows the built-in `anthropic`, `openai` and
+/// `ollama` types; library users can [`register`](Self::register) their own.
+#[derive(Clone)]
+pub struct ProviderRegistry {
+    factories: HashMap<String, ProviderFactory>,
+}
+
+impl ProviderRegistry {
+    /// Create a registry with no provider types
+    pub fn empty() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Register a constructor for `provider_type`, replacing any existing one
+    pub fn register<F>(&mut self, provider_type: impl Into<String>, factory: F)
+    where
+        F: Fn(&ProviderConfig) -> Result<Box<dyn LLMProvider>> + Send + Sync + 'static,
+    {
+        self.factories
+            .insert(provider_type.into(), Arc::new(factory));
+    }
+
+    /// Whether a constructor is registered for `provider_type`
+    pub fn contains(&self, provider_type: &str) -> bool {
+        self.factories.contains_key(provider_type)
+    }
+
+    /// Registered provider types, sorted
+    pub fn types(&self) -> Vec<&str> {
+        let mut types: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+        types.sort_unstable();
+        types
+    }
+
+    /// Build a provider from its configuration, wrapping it in a
+    /// [`RetryProvider`] when a retry policy is configured. An API key
+    /// reference not resolved yet is resolved first.
+    pub fn create(&self, config: &ProviderConfig) -> Result<Box<dyn LLMProvider>> {
+        let factory = self.factories.get(&config.provider_type).ok_or_else(|| {
+            SkynetError::config(format!(
+                "Unknown provider type '{}' (known: {})",
+                config.provider_type,
+                self.types().join(", ")
+            ))
+        })?;
+
+        info!(
+            "Creating {} provider with model {}",
+            config.provider_type, config.model
+        );
+        let provider = if config.api_key.is_resolved() {
+            factory(config)?
+        } else {
+            factory(&ProviderConfig {
+                api_key: config.api_key.resolved()?,
+                ..config.clone()
+            })?
+        };
+
+        match &config.retry {
+            Some(retry) if retry.max_retries > 0 => {
+                Ok(Box::new(RetryProvider::new(provider, retry.clone())))
+            }
+            _ => Ok(provider),
+        }
+    }
+
+    /// Build the provider the agent should use: the active provider, or a
+    /// [`FallbackProvider`] chain when `[fallback]` lists more providers
+    pub fn create_from_config(&self, config: &Config) -> Result<Box<dyn LLMProvider>> {
+        let primary = self.create(&config.active_provider()?)?;
+
+        let Some(fallback) = config.fallback.as_ref().filter(|f| !f.providers.is_empty()) else {
+            return Ok(primary);
+        };
+
+        let mut chain =
+            FallbackProvider::new(fallback).with_provider(config.active_provider_name(), primary);
+        for name in &fallback.providers {
+            let provider = self.create(&config.resolve_provider(Some(name))?)?;
+            chain = chain.with_provider(name.clone(), provider);
+        }
+        info!(
+            "Provider fallback chain: {}",
+            chain.provider_names().join(" -> ")
+        );
+        Ok(Box::new(chain))
+    }
+}
+
+impl Default for ProviderRegistry {
+    /// Registry with the built-in provider types
+    fn default() -> Self {
+        let mut registry = Self::empty();
+
+        registry.register("anthropic", |config| {
+            let mut provider = AnthropicProvider::new(config.api_key.clone())?
+                .with_options(config.generation_options());
+            if let Some(base_url) = &config.base_url {
+                provider = provider.with_base_url(base_url.clone());
+            }
+            Ok(Box::new(provider))
+        });
+
+        registry.register("openai", |config| {
+            let mut provider = OpenAIProvider::new(config.api_key.clone(), config.model.clone())?
+                .with_options(config.generation_options());
+            if let Some(base_url) = &config.base_url {
+                provider = provider.with_base_url(base_url.clone());
+            }
+            Ok(Box::new(provider))
+        });
+
+        registry.register("ollama", |config| {
+            let mut provider = OllamaProvider::new(config.model.clone())?
+                .with_options(config.generation_options());
+            if let Some(base_url) = &config.base_url {
+                provider = provider.with_base_url(base_url.clone());
+            }
+            Ok(Box::new(provider))
+        });
+
+        registry
+    }
+}
@@ -0,0 +1,392 @@
+use crate::core::message::Message;
+use crate::providers::error::ProviderError;
+use crate::providers::{CompletionRequest, LLMProvider, ResponseStream};
+use crate::{Result, SkynetError};
+use async_trait::async_trait;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Kinds of provider failure a [`RetryConfig`] can opt into retrying
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// HTTP 429 / `rate_limit_error`
+    RateLimited,
+    /// HTTP 503, 529 / `overloaded_error`
+    Overloaded,
+    /// Connection failures and timeouts
+    Network,
+    /// Other 5xx responses and 408 timeouts
+    ServerError,
+    /// Responses that could not be parsed
+    InvalidResponse,
+}
+
+impl ErrorClass {
+    /// Class of a provider error, or `None` if it is never worth retrying
+    pub fn of(error: &SkynetError) -> Option<Self> {
+        match error {
+            SkynetError::Provider(error) => match error {
+                ProviderError::RateLimited { .. } => Some(Self::RateLimited),
+                ProviderError::Overloaded { .. } => Some(Self::Overloaded),
+                ProviderError::Network(_) => Some(Self::Network),
+                ProviderError::Api { status, .. } if *status == 408 || *status >= 500 => {
+                    Some(Self::ServerError)
+                }
+                ProviderError::InvalidResponse { .. } => Some(Self::InvalidResponse),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Retry policy for provider calls (`[agent.retry]` or `[providers.<name>.retry]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Retries after the first attempt; 0 disables retrying
+    pub max_retries: u32,
+
+    /// Delay before the first retry, in milliseconds
+    pub initial_backoff_ms: u64,
+
+    /// Upper bound for a single delay, in milliseconds
+    pub max_backoff_ms: u64,
+
+    /// Factor the delay grows by after each retry
+    pub multiplier: f64,
+
+    /// Fraction of each delay (0.0-1.0) that is randomized
+    pub jitter: f64,
+
+    /// Give up once this many seconds have passed since the first attempt
+    pub max_elapsed_secs: u64,
+
+    /// Error classes that are retried; anything else fails immediately
+    pub retry_on: Vec<ErrorClass>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_elapsed_secs: 120,
+            retry_on: vec![
+                ErrorClass::RateLimited,
+                ErrorClass::Overloaded,
+                ErrorClass::Network,
+                ErrorClass::ServerError,
+            ],
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Exponential delay before retry number `retry` (starting at 1), capped
+    /// at `max_backoff_ms`, with the `jitter` fraction randomized
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let base = self.initial_backoff_ms as f64 * self.multiplier.max(1.0).powi(exponent);
+        let capped = base.min(self.max_backoff_ms as f64);
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let randomized = capped * (1.0 - jitter * rand::thread_rng().gen::<f64>());
+        Duration::from_millis(randomized as u64)
+    }
+
+    /// Delay before retrying `error`, or `None` if it should not be retried.
+    ///
+    /// A server-provided `retry-after` takes precedence over the backoff.
+    fn delay_for(&self, error: &SkynetError, retry: u32, elapsed: Duration) -> Option<Duration> {
+        if retry > self.max_retries {
+            return None;
+        }
+        let class = ErrorClass::of(error)?;
+        if !self.retry_on.contains(&class) {
+            return None;
+        }
+
+        let delay = match error {
+            SkynetError::Provider(ProviderError::RateLimited {
+                retry_after: Some(retry_after),
+                ..
+            }) => *retry_after,
+            _ => self.backoff(retry),
+        };
+
+        let budget = Duration::from_secs(self.max_elapsed_secs);
+        (elapsed + delay <= budget).then_some(delay)
+    }
+}
+
+/// Provider wrapper that retries transient failures of the inner provider.
+///
+/// Only establishing a stream is retried; errors in the middle of a stream
+/// are passed through, since events may already have been consumed.
+pub struct RetryProvider {
+    inner: Box<dyn LLMProvider>,
+    config: RetryConfig,
+}
+
+impl RetryProvider {
+    /// Wrap `inner` with the given retry policy
+    pub fn new(inner: Box<dyn LLMProvider>, config: RetryConfig) -> Self {
+        Self { inner, config }
+    }
+
+    /// Run `call` until it succeeds or the policy gives up
+    async fn with_retries<T, F, Fut>(&self, mut call: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let started = Instant::now();
+        let mut retry = 0;
+
+        loop {
+            let error = match call().await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+
+            retry += 1;
+            let Some(delay) = self.config.delay_for(&error, retry, started.elapsed()) else {
+                if retry > 1 {
+                    warn!(
+                        "{} request failed after {} attempts: {}",
+                        self.inner.name(),
+                        retry,
+                        error
+                    );
+                }
+                return Err(error);
+            };
+
+            warn!(
+                "{} request failed ({}), retrying in {:?} (retry {}/{})",
+                self.inner.name(),
+                error,
+                delay,
+                retry,
+                self.config.max_retries
+            );
+            tokio::time::sleep(delay).await;
+            debug!("Retrying {} request", self.inner.name());
+        }
+    }
+}
+
+#[async_trait]
+impl LLMProvider for RetryProvider {
+    async fn complete(&self, request: &CompletionRequest) -> Result<Message> {
+        self.with_retries(|| self.inner.complete(request)).await
+    }
+
+    async fn generate_stream(&self, request: &CompletionRequest) -> Result<ResponseStream> {
+        self.with_retries(|| self.inner.generate_stream(request))
+            .await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    /// Provider failing with queued errors in order, then replying "ok"
+    #[derive(Clone, Default)]
+    struct Flaky {
+        errors: Arc<Mutex<VecDeque<ProviderError>>>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Flaky {
+        fn fail(&self, error: ProviderError) -> &Self {
+            self.errors.lock().unwrap().push_back(error);
+            self
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for Flaky {
+        async fn complete(&self, _request: &CompletionRequest) -> Result<Message> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.errors.lock().unwrap().pop_front() {
+                Some(error) => Err(SkynetError::Provider(error)),
+                None => Ok(Message::assistant("ok".to_string())),
+            }
+        }
+
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn model(&self) -> &str {
+            "flaky-model"
+        }
+
+        async fn health_check(&self) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    fn overloaded() -> ProviderError {
+        ProviderError::Overloaded {
+            message: "overloaded".to_string(),
+        }
+    }
+
+    /// Fast, deterministic policy
+    fn policy(max_retries: u32) -> RetryConfig {
+        RetryConfig {
+            max_retries,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 5,
+            jitter: 0.0,
+            ..RetryConfig::default()
+        }
+    }
+
+    async fn complete(flaky: &Flaky, config: RetryConfig) -> Result<Message> {
+        RetryProvider::new(Box::new(flaky.clone()), config)
+            .complete(&CompletionRequest::default())
+            .await
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_until_success() {
+        let flaky = Flaky::default();
+        flaky.fail(overloaded()).fail(ProviderError::Api {
+            status: 502,
+            message: "bad gateway".to_string(),
+        });
+
+        let reply = complete(&flaky, policy(3)).await.unwrap();
+        assert_eq!(reply.content, "ok");
+        assert_eq!(flaky.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let flaky = Flaky::default();
+        for _ in 0..5 {
+            flaky.fail(overloaded());
+        }
+
+        let error = complete(&flaky, policy(2)).await.unwrap_err();
+        assert!(matches!(
+            error,
+            SkynetError::Provider(ProviderError::Overloaded { .. })
+        ));
+        assert_eq!(flaky.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_permanent_errors() {
+        let permanent = [
+            ProviderError::Authentication {
+                message: "bad key".to_string(),
+            },
+            ProviderError::InvalidRequest {
+                message: "bad request".to_string(),
+            },
+            ProviderError::ContextTooLong {
+                message: "too long".to_string(),
+            },
+            ProviderError::Api {
+                status: 404,
+                message: "not found".to_string(),
+            },
+            // Not in the default `retry_on`
+            ProviderError::InvalidResponse {
+                message: "garbled".to_string(),
+            },
+        ];
+        for error in permanent {
+            let flaky = Flaky::default();
+            flaky.fail(error);
+            assert!(complete(&flaky, policy(3)).await.is_err());
+            assert_eq!(flaky.calls(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_retry_after_exceeds_the_time_budget() {
+        let flaky = Flaky::default();
+        flaky.fail(ProviderError::RateLimited {
+            message: "slow down".to_string(),
+            retry_after: Some(Duration::from_secs(60)),
+        });
+
+        let config = RetryConfig {
+            max_elapsed_secs: 1,
+            ..policy(3)
+        };
+        assert!(complete(&flaky, config).await.is_err());
+        assert_eq!(flaky.calls(), 1);
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_the_cap() {
+        let config = RetryConfig {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 300,
+            multiplier: 2.0,
+            jitter: 0.0,
+            ..RetryConfig::default()
+        };
+        let delays: Vec<u128> = (1..=4)
+            .map(|retry| config.backoff(retry).as_millis())
+            .collect();
+        assert_eq!(delays, [100, 200, 300, 300]);
+
+        let jittered = RetryConfig {
+            jitter: 0.5,
+            ..config
+        };
+        for _ in 0..20 {
+            let delay = jittered.backoff(1).as_millis();
+            assert!((50..=100).contains(&delay), "{} out of range", delay);
+        }
+    }
+
+    #[test]
+    fn classifies_errors() {
+        let class = |error| ErrorClass::of(&SkynetError::Provider(error));
+        let api = |status| ProviderError::Api {
+            status,
+            message: String::new(),
+        };
+        assert_eq!(class(overloaded()), Some(ErrorClass::Overloaded));
+        assert_eq!(class(api(500)), Some(ErrorClass::ServerError));
+        assert_eq!(class(api(408)), Some(ErrorClass::ServerError));
+        assert_eq!(class(api(404)), None);
+        assert_eq!(ErrorClass::of(&SkynetError::config("nope")), None);
+    }
+}
@@ -0,0 +1,100 @@
+//! Scripted provider for unit tests
+
+use crate::core::message::Message;
+use crate::providers::{CompletionRequest, LLMProvider, ProviderError};
+use crate::{Result, SkynetError};
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Provider returning queued outcomes in order, then "ok" replies. Clones
+/// share their script and counters.
+#[derive(Clone)]
+pub(crate) struct ScriptedProvider {
+    name: String,
+    outcomes: Arc<Mutex<VecDeque<Result<Message>>>>,
+    requests: Arc<Mutex<Vec<CompletionRequest>>>,
+    calls: Arc<AtomicUsize>,
+    health_checks: Arc<AtomicUsize>,
+    healthy: Arc<AtomicBool>,
+}
+
+impl ScriptedProvider {
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            outcomes: Arc::default(),
+            requests: Arc::default(),
+            calls: Arc::default(),
+            health_checks: Arc::default(),
+            healthy: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Fail the next call with `error`
+    pub(crate) fn fail(&self, error: ProviderError) -> &Self {
+        self.outcomes
+            .lock()
+            .unwrap()
+            .push_back(Err(SkynetError::Provider(error)));
+        self
+    }
+
+    /// Answer the next call with `message`
+    pub(crate) fn reply(&self, message: Message) -> &Self {
+        self.outcomes.lock().unwrap().push_back(Ok(message));
+        self
+    }
+
+    pub(crate) fn set_healthy(&self, healthy: bool) {
+        self.healthy.store(healthy, Ordering::SeqCst);
+    }
+
+    /// Calls to `complete` so far
+    pub(crate) fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// Requests passed to `complete` so far
+    pub(crate) fn requests(&self) -> Vec<CompletionRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub(crate) fn health_checks(&self) -> usize {
+        self.health_checks.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn boxed(&self) -> Box<dyn LLMProvider> {
+        Box::new(self.clone())
+    }
+}
+
+pub(crate) fn overloaded() -> ProviderError {
+    ProviderError::Overloaded {
+        message: "overloaded".to_string(),
+    }
+}
+
+#[async_trait]
+impl LLMProvider for ScriptedProvider {
+    async fn complete(&self, request: &CompletionRequest) -> Result<Message> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.requests.lock().unwrap().push(request.clone());
+        let outcome = self.outcomes.lock().unwrap().pop_front();
+        outcome.unwrap_or_else(|| Ok(Message::assistant(format!("ok from {}", self.name))))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn model(&self) -> &str {
+        "scripted-model"
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        self.health_checks.fetch_add(1, Ordering::SeqCst);
+        Ok(self.healthy.load(Ordering::SeqCst))
+    }
+}
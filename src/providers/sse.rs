@@ -0,0 +1,211 @@
+//! Minimal server-sent events decoder shared by streaming providers
+
+use crate::{Result, SkynetError};
+use futures_util::stream::{self, Stream, StreamExt};
+use std::collections::VecDeque;
+
+/// A single decoded server-sent event
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Incremental decoder turning raw bytes into SSE events
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    /// Create an empty decoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of bytes and return every event it completes
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if let Some(event) = self.process_line(line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Flush a trailing event that was not terminated by a blank line
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        if !self.buffer.is_empty() {
+            let line = String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).into_owned();
+            if let Some(event) = self.process_line(line.trim_end_matches('\r')) {
+                return Some(event);
+            }
+        }
+        self.dispatch()
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            // Comment line, used by servers as keep-alive
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        if self.event.is_none() && self.data.is_empty() {
+            return None;
+        }
+        Some(SseEvent {
+            event: self.event.take(),
+            data: std::mem::take(&mut self.data).join("\n"),
+        })
+    }
+}
+
+/// Decode a byte stream (e.g. `reqwest::Response::bytes_stream`) into SSE events
+pub fn event_stream<S, B, E>(bytes: S) -> impl Stream<Item = Result<SseEvent>> + Send
+where
+    S: Stream<Item = std::result::Result<B, E>> + Send + Unpin + 'static,
+    B: AsRef<[u8]>,
+    E: Into<SkynetError> + Send + 'static,
+{
+    let state = (bytes, SseDecoder::new(), VecDeque::new(), false);
+
+    stream::unfold(
+        state,
+        |(mut bytes, mut decoder, mut pending, mut finished)| async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    return Some((Ok(event), (bytes, decoder, pending, finished)));
+                }
+                if finished {
+                    return None;
+                }
+
+                match bytes.next().await {
+                    Some(Ok(chunk)) => pending.extend(decoder.push(chunk.as_ref())),
+                    Some(Err(e)) => {
+                        finished = true;
+                        return Some((Err(e.into()), (bytes, decoder, pending, finished)));
+                    }
+                    None => {
+                        finished = true;
+                        pending.extend(decoder.finish());
+                    }
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event: Option<&str>, data: &str) -> SseEvent {
+        SseEvent {
+            event: event.map(str::to_string),
+            data: data.to_string(),
+        }
+    }
+
+    /// Decode `chunks` fed one after the other, then finish
+    fn decode(chunks: &[&str]) -> Vec<SseEvent> {
+        let mut decoder = SseDecoder::new();
+        let mut events: Vec<SseEvent> = chunks
+            .iter()
+            .flat_map(|chunk| decoder.push(chunk.as_bytes()))
+            .collect();
+        events.extend(decoder.finish());
+        events
+    }
+
+    #[test]
+    fn reassembles_events_split_across_chunks() {
+        let events = decode(&[
+            "event: ping\nda",
+            "ta: {\"a\"",
+            ":1}\n",
+            "\nevent: done\n\n",
+        ]);
+        assert_eq!(
+            events,
+            [event(Some("ping"), "{\"a\":1}"), event(Some("done"), "")]
+        );
+
+        // Split inside a multi-byte character
+        let bytes = "data: é\n\n".as_bytes();
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.push(&bytes[..7]).is_empty());
+        assert_eq!(decoder.push(&bytes[7..]), [event(None, "é")]);
+    }
+
+    #[test]
+    fn joins_multi_line_data_with_newlines() {
+        let events = decode(&["data: first\ndata: second\ndata:third\n\n"]);
+        assert_eq!(events, [event(None, "first\nsecond\nthird")]);
+    }
+
+    #[test]
+    fn accepts_crlf_line_endings() {
+        let events = decode(&[
+            "event: message\r\ndata: one\r\n",
+            "data: two\r\n\r\ndata: x\r\n\r\n",
+        ]);
+        assert_eq!(
+            events,
+            [event(Some("message"), "one\ntwo"), event(None, "x")]
+        );
+    }
+
+    #[test]
+    fn ignores_comments_and_unknown_fields() {
+        let events = decode(&[": keep-alive\n\n", "id: 7\nretry: 10\n:note\ndata: hi\n\n"]);
+        assert_eq!(events, [event(None, "hi")]);
+    }
+
+    #[test]
+    fn flushes_an_unterminated_event_at_the_end() {
+        assert_eq!(decode(&["data: last"]), [event(None, "last")]);
+        assert_eq!(decode(&["data: last\r\n"]), [event(None, "last")]);
+        assert!(decode(&[""]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn decodes_a_byte_stream_and_passes_errors_on() {
+        let chunks: Vec<std::result::Result<&[u8], SkynetError>> = vec![
+            Ok(b"data: a\n\nda"),
+            Ok(b"ta: b\n\n"),
+            Err(SkynetError::agent("connection reset")),
+        ];
+        let results: Vec<Result<SseEvent>> = event_stream(stream::iter(chunks)).collect().await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), &event(None, "a"));
+        assert_eq!(results[1].as_ref().unwrap(), &event(None, "b"));
+        assert!(results[2].is_err());
+    }
+}
@@ -1,29 +1,422 @@
-use crate::core::message::Message;
+use crate::config::Secret;
+use crate::core::message::{Message, ToolCall};
+use crate::providers::error::ProviderError;
+use crate::providers::fallback::PROVIDER_METADATA_KEY;
+use crate::providers::retry::RetryConfig;
 use crate::Result;
 use async_trait::async_trait;
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::pin::Pin;
+
+/// Stream of incremental events produced by a streaming generation
+pub type ResponseStream = Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>;
 
 /// LLM Provider trait for different AI services
 #[async_trait]
 pub trait LLMProvider: Send + Sync {
-    /// Generate a response from the conversation history
-    async fn generate(&self, messages: &[Message]) -> Result<String>;
-    
+    /// Complete a request, returning the assistant message.
+    ///
+    /// When the model asks for tools the returned message has
+    /// `MessageType::ToolCall` and carries the calls in its metadata. Token
+    /// usage and the serving model are recorded in `metadata["usage"]` and
+    /// `metadata["model"]`.
+    async fn complete(&self, request: &CompletionRequest) -> Result<Message>;
+
+    /// Generate a text response from the conversation history
+    async fn generate(&self, messages: &[Message]) -> Result<Generation> {
+        let response = self
+            .complete(&CompletionRequest::new(messages.to_vec()))
+            .await?;
+        Ok(Generation {
+            usage: response.usage().unwrap_or_default(),
+            text: response.content,
+        })
+    }
+
+    /// Complete a request as a stream of incremental events.
+    ///
+    /// The default implementation waits for [`complete`](Self::complete) and
+    /// replays the finished message as a handful of events.
+    async fn generate_stream(&self, request: &CompletionRequest) -> Result<ResponseStream> {
+        let response = self.complete(request).await?;
+
+        let mut events = Vec::new();
+        if !response.content.is_empty() {
+            events.push(Ok(StreamEvent::TextDelta(response.content.clone())));
+        }
+        for (index, call) in response.tool_calls().into_iter().enumerate() {
+            events.push(Ok(StreamEvent::ToolCallStart {
+                index,
+                id: call.id,
+                name: call.name,
+            }));
+            events.push(Ok(StreamEvent::ToolCallDelta {
+                index,
+                partial_json: call.arguments.to_string(),
+            }));
+        }
+        events.push(Ok(StreamEvent::Done {
+            stop_reason: None,
+            usage: response.usage().unwrap_or_default(),
+            model: response.model().map(str::to_string),
+            provider: response
+                .metadata
+                .get(PROVIDER_METADATA_KEY)
+                .and_then(|name| name.as_str())
+                .map(str::to_string),
+        }));
+
+        Ok(Box::pin(stream::iter(events)))
+    }
+
     /// Get the provider name
     fn name(&self) -> &str;
-    
+
     /// Get the model being used
     fn model(&self) -> &str;
-    
+
     /// Check if the provider is healthy/available
     async fn health_check(&self) -> Result<bool>;
 }
 
+/// A request sent to an LLM provider
+#[derive(Debug, Clone, Default)]
+pub struct CompletionRequest {
+    /// Conversation history, including system messages
+    pub messages: Vec<Message>,
+
+    /// Tools the model may call
+    pub tools: Vec<ToolDefinition>,
+
+    /// Per-request overrides of the provider's default generation options
+    pub options: GenerationOptions,
+}
+
+impl CompletionRequest {
+    /// Create a request for the given conversation with no tools
+    pub fn new(messages: Vec<Message>) -> Self {
+        Self {
+            messages,
+            tools: Vec::new(),
+            options: GenerationOptions::default(),
+        }
+    }
+
+    /// Offer the given tools to the model
+    pub fn with_tools(mut self, tools: Vec<ToolDefinition>) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Override generation options for this request
+    pub fn with_options(mut self, options: GenerationOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+/// Sampling and output settings for a generation.
+///
+/// Unset fields fall back to the provider's defaults, which in turn come from
+/// `ProviderConfig` and `AgentConfig`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+    /// Request metadata passed through to providers that support it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl GenerationOptions {
+    /// Fill every unset field of `self` from `defaults`
+    pub fn with_defaults(&self, defaults: &GenerationOptions) -> GenerationOptions {
+        GenerationOptions {
+            model: self.model.clone().or_else(|| defaults.model.clone()),
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+            stop_sequences: if self.stop_sequences.is_empty() {
+                defaults.stop_sequences.clone()
+            } else {
+                self.stop_sequences.clone()
+            },
+            metadata: if self.metadata.is_empty() {
+                defaults.metadata.clone()
+            } else {
+                self.metadata.clone()
+            },
+        }
+    }
+}
+
+/// Tool description sent to the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub input_schema: serde_json::Value,
+}
+
+/// Incremental event emitted while streaming a generation
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// A chunk of assistant text
+    TextDelta(String),
+
+    /// The model started a tool call. `index` identifies the call within the
+    /// response but need not be contiguous: Anthropic numbers every content
+    /// block, text included
+    ToolCallStart {
+        index: usize,
+        id: String,
+        name: String,
+    },
+
+    /// A fragment of the JSON arguments for the tool call at `index`
+    ToolCallDelta { index: usize, partial_json: String },
+
+    /// Generation finished; always the last event of a successful stream
+    Done {
+        stop_reason: Option<String>,
+        usage: Usage,
+        /// Provider that served the stream, when a fallback chain chose it
+        provider: Option<String>,
+        /// Model that served the stream, when the provider reports it
+        model: Option<String>,
+    },
+}
+
+/// Builds the finished assistant message from streamed events
+#[derive(Debug, Default)]
+pub struct StreamAccumulator {
+    text: String,
+    /// (id, name, argument JSON) of each started tool call, by index
+    calls: BTreeMap<usize, (String, String, String)>,
+    /// Set by the `Done` event
+    usage: Option<Usage>,
+    provider: Option<String>,
+    model: Option<String>,
+}
+
+impl StreamAccumulator {
+    /// Create an empty accumulator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next event of the stream
+    pub fn push(&mut self, event: StreamEvent) {
+        match event {
+            StreamEvent::TextDelta(text) => self.text.push_str(&text),
+            StreamEvent::ToolCallStart { index, id, name } => {
+                self.calls.insert(index, (id, name, String::new()));
+            }
+            StreamEvent::ToolCallDelta {
+                index,
+                partial_json,
+            } => {
+                if let Some(call) = self.calls.get_mut(&index) {
+                    call.2.push_str(&partial_json);
+                }
+            }
+            StreamEvent::Done {
+                usage,
+                provider,
+                model,
+                ..
+            } => {
+                self.usage = Some(usage);
+                self.provider = provider;
+                self.model = model;
+            }
+        }
+    }
+
+    /// The assistant message the stream produced, attributed to the model
+    /// named by its `Done` event, or to `model` when it named none.
+    ///
+    /// Fails if the stream ended without a `Done` event, since the response
+    /// is then incomplete.
+    pub fn into_message(self, model: &str) -> Result<Message> {
+        let Some(usage) = self.usage else {
+            return Err(ProviderError::InvalidResponse {
+                message: "Stream ended before the response was complete".to_string(),
+            }
+            .into());
+        };
+
+        let mut calls = Vec::with_capacity(self.calls.len());
+        for (id, name, arguments) in self.calls.into_values() {
+            let arguments = if arguments.trim().is_empty() {
+                serde_json::json!({})
+            } else {
+                serde_json::from_str(&arguments).map_err(ProviderError::from)?
+            };
+            calls.push(ToolCall {
+                id,
+                name,
+                arguments,
+            });
+        }
+
+        let message = if calls.is_empty() {
+            Message::assistant(self.text)
+        } else {
+            Message::tool_call(self.text, calls)
+        };
+        let model = self.model.as_deref().unwrap_or(model);
+        let message = message.with_usage(model, &usage);
+        Ok(match self.provider {
+            Some(provider) => {
+                message.with_metadata(PROVIDER_METADATA_KEY.to_string(), provider.into())
+            }
+            None => message,
+        })
+    }
+}
+
+/// Token usage reported by a provider
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Prompt tokens not served from the prompt cache
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    /// Prompt tokens written to the prompt cache
+    #[serde(default)]
+    pub cache_creation_input_tokens: u32,
+    /// Prompt tokens read from the prompt cache
+    #[serde(default)]
+    pub cache_read_input_tokens: u32,
+}
+
+impl Usage {
+    /// All tokens billed for the request
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens as u64
+            + self.output_tokens as u64
+            + self.cache_creation_input_tokens as u64
+            + self.cache_read_input_tokens as u64
+    }
+}
+
+impl std::ops::AddAssign<&Usage> for Usage {
+    fn add_assign(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
+}
+
+/// Text of a completion and the tokens it used
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Generation {
+    pub text: String,
+    pub usage: Usage,
+}
+
 /// Configuration for LLM providers
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
-    pub api_key: String,
+    /// Provider type registered in the `ProviderRegistry`, e.g. `anthropic`
+    #[serde(rename = "type")]
+    pub provider_type: String,
+    /// API key, literal or an `env:`, `file:` or `cmd:` reference
+    #[serde(default)]
+    pub api_key: Secret,
     pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::config::short_float::serialize_option"
+    )]
     pub temperature: Option<f32>,
-}
\ No newline at end of file
+    /// Context window of `model` in tokens; falls back to `[agent]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<usize>,
+    /// Retry policy; falls back to `[agent.retry]` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryConfig>,
+}
+
+impl ProviderConfig {
+    /// Default generation options configured for this provider
+    pub fn generation_options(&self) -> GenerationOptions {
+        GenerationOptions {
+            model: Some(self.model.clone()),
+            max_tokens: self.max_tokens,
+            temperature: self.temperature,
+            ..GenerationOptions::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::scripted::ScriptedProvider;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn default_stream_replays_the_usage_and_model_of_the_reply() {
+        let usage = Usage {
+            input_tokens: 12,
+            output_tokens: 7,
+            ..Usage::default()
+        };
+        let scripted = ScriptedProvider::new("primary");
+        scripted.reply(Message::assistant("Hello".to_string()).with_usage("served-model", &usage));
+
+        let events: Vec<StreamEvent> = scripted
+            .generate_stream(&CompletionRequest::default())
+            .await
+            .unwrap()
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+        assert_eq!(
+            events,
+            [
+                StreamEvent::TextDelta("Hello".to_string()),
+                StreamEvent::Done {
+                    stop_reason: None,
+                    usage: usage.clone(),
+                    provider: None,
+                    model: Some("served-model".to_string()),
+                },
+            ]
+        );
+
+        let mut accumulator = StreamAccumulator::new();
+        events.into_iter().for_each(|event| accumulator.push(event));
+        let message = accumulator.into_message("configured-model").unwrap();
+        assert_eq!(message.model(), Some("served-model"));
+        assert_eq!(message.usage(), Some(usage));
+    }
+
+    #[test]
+    fn a_stream_without_done_is_incomplete() {
+        let mut accumulator = StreamAccumulator::new();
+        accumulator.push(StreamEvent::TextDelta("Hel".to_string()));
+        let error = accumulator.into_message("model").unwrap_err();
+        assert!(matches!(
+            error,
+            crate::SkynetError::Provider(ProviderError::InvalidResponse { .. })
+        ));
+    }
+}
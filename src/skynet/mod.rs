@@ -2,4 +2,4 @@
 
 pub mod pulse;
 
-pub use pulse::Pulse;
\ No newline at end of file
+pub use pulse::Pulse;
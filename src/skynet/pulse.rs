@@ -1,11 +1,13 @@
-use crate::Result;
+use crate::{Result, SkynetError};
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
-use tracing::{info, debug, warn};
+use tokio::sync::{Notify, RwLock};
+use tracing::{debug, info, warn};
 
 /// SKYNET Pulse - Heartbeat monitoring system
 pub struct Pulse {
-    interval: Duration,
+    interval: RwLock<Duration>,
+    /// Wakes the monitor loop when the interval changes
+    interval_changed: Notify,
     last_pulse: RwLock<Option<Instant>>,
     pulse_count: RwLock<u64>,
     running: RwLock<bool>,
@@ -15,18 +17,14 @@ impl Pulse {
     /// Create a new Pulse monitor
     pub fn new(interval: Duration) -> Self {
         Self {
-            interval,
+            interval: RwLock::new(interval),
+            interval_changed: Notify::new(),
             last_pulse: RwLock::new(None),
             pulse_count: RwLock::new(0),
             running: RwLock::new(false),
         }
     }
 
-    /// Create a pulse with default 30-second interval
-    pub fn default() -> Self {
-        Self::new(Duration::from_secs(30))
-    }
-
     /// Start the pulse monitoring
     pub async fn start(&self) -> Result<()> {
         let mut running = self.running.write().await;
@@ -36,11 +34,14 @@ impl Pulse {
         }
 
         *running = true;
-        info!("🫀 SKYNET Pulse started (interval: {:?})", self.interval);
-        
+        info!(
+            "🫀 SKYNET Pulse started (interval: {:?})",
+            *self.interval.read().await
+        );
+
         let mut last_pulse = self.last_pulse.write().await;
         *last_pulse = Some(Instant::now());
-        
+
         Ok(())
     }
 
@@ -51,15 +52,30 @@ impl Pulse {
         info!("💔 SKYNET Pulse stopped");
     }
 
+    /// Change the heartbeat interval, taking effect right away
+    pub async fn set_interval(&self, interval: Duration) {
+        let mut current = self.interval.write().await;
+        if *current != interval {
+            info!("🫀 SKYNET Pulse interval changed to {:?}", interval);
+            *current = interval;
+            self.interval_changed.notify_one();
+        }
+    }
+
+    /// Current heartbeat interval
+    pub async fn interval(&self) -> Duration {
+        *self.interval.read().await
+    }
+
     /// Record a heartbeat
     pub async fn heartbeat(&self) -> Result<()> {
         let running = self.running.read().await;
         if !*running {
-            return Err("Pulse is not running".into());
+            return Err(SkynetError::agent("Pulse is not running"));
         }
 
         let now = Instant::now();
-        
+
         // Update last pulse time
         {
             let mut last_pulse = self.last_pulse.write().await;
@@ -87,7 +103,7 @@ impl Pulse {
         if let Some(last) = *last_pulse {
             let elapsed = last.elapsed();
             // Consider unhealthy if no pulse for 2x the interval
-            elapsed < (self.interval * 2)
+            elapsed < (*self.interval.read().await * 2)
         } else {
             false
         }
@@ -98,7 +114,7 @@ impl Pulse {
         let running = *self.running.read().await;
         let count = *self.pulse_count.read().await;
         let last_pulse = *self.last_pulse.read().await;
-        
+
         let time_since_last = last_pulse.map(|t| t.elapsed());
         let is_healthy = self.is_healthy().await;
 
@@ -106,7 +122,7 @@ impl Pulse {
             running,
             pulse_count: count,
             time_since_last,
-            interval: self.interval,
+            interval: self.interval().await,
             is_healthy,
         }
     }
@@ -129,8 +145,12 @@ impl Pulse {
                 warn!("⚠️  SKYNET Pulse health warning");
             }
 
-            // Sleep until next pulse
-            tokio::time::sleep(self.interval).await;
+            // Sleep until next pulse, or until the interval changes
+            let interval = self.interval().await;
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = self.interval_changed.notified() => {}
+            }
         }
 
         info!("SKYNET Pulse monitor stopped");
@@ -138,6 +158,13 @@ impl Pulse {
     }
 }
 
+impl Default for Pulse {
+    /// Create a pulse with default 30-second interval
+    fn default() -> Self {
+        Self::new(Duration::from_secs(30))
+    }
+}
+
 /// Pulse statistics
 #[derive(Debug, Clone)]
 pub struct PulseStats {
@@ -159,4 +186,4 @@ impl std::fmt::Display for PulseStats {
             if self.is_healthy { "YES" } else { "NO" }
         )
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,347 @@
+use async_trait::async_trait;
+use serde_json::json;
+use skynet_rust::config::ConfigChanges;
+use skynet_rust::core::agent::Tool;
+use skynet_rust::providers::{CompletionRequest, LLMProvider, ProviderRegistry};
+use skynet_rust::{Config, Message, MessageType, Result, SkynetAgent, ToolCall};
+use std::collections::{HashSet, VecDeque};
+use std::io;
+use std::sync::{Arc, Mutex};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Replies with scripted messages in order, recording every request
+#[derive(Clone, Default)]
+struct ScriptedProvider {
+    replies: Arc<Mutex<VecDeque<Message>>>,
+    requests: Arc<Mutex<Vec<CompletionRequest>>>,
+}
+
+impl ScriptedProvider {
+    fn reply(&self, message: Message) {
+        self.replies.lock().unwrap().push_back(message);
+    }
+
+    fn requests(&self) -> Vec<CompletionRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl LLMProvider for ScriptedProvider {
+    async fn complete(&self, request: &CompletionRequest) -> Result<Message> {
+        self.requests.lock().unwrap().push(request.clone());
+        Ok(self
+            .replies
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("no scripted reply left"))
+    }
+
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    fn model(&self) -> &str {
+        "scripted-model"
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Ok(true)
+    }
+}
+
+struct EchoTool;
+
+#[async_trait]
+impl Tool for EchoTool {
+    async fn execute(&self, args: serde_json::Value) -> Result<String> {
+        Ok(args.to_string())
+    }
+
+    fn name(&self) -> &str {
+        "echo"
+    }
+
+    fn description(&self) -> &str {
+        "Echo the arguments"
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        json!({ "type": "object" })
+    }
+}
+
+fn config(extra: &str) -> Config {
+    toml::from_str(&format!(
+        r#"
+provider = "scripted"
+
+[providers.scripted]
+type = "scripted"
+model = "scripted-model"
+
+[database]
+backend = "memory"
+
+{}
+"#,
+        extra
+    ))
+    .unwrap()
+}
+
+async fn agent(config: Config, provider: &ScriptedProvider) -> SkynetAgent {
+    let mut registry = ProviderRegistry::empty();
+    let scripted = provider.clone();
+    registry.register("scripted", move |_| Ok(Box::new(scripted.clone())));
+    SkynetAgent::with_registry(config, &registry).await.unwrap()
+}
+
+fn echo_call(id: &str) -> Message {
+    Message::tool_call(
+        String::new(),
+        vec![ToolCall {
+            id: id.to_string(),
+            name: "echo".to_string(),
+            arguments: json!({ "text": id }),
+        }],
+    )
+}
+
+/// IDs of tool calls in `messages` that no tool result answers
+fn unanswered_calls(messages: &[Message]) -> Vec<String> {
+    let answered: HashSet<&str> = messages.iter().filter_map(Message::tool_call_id).collect();
+    messages
+        .iter()
+        .flat_map(Message::tool_calls)
+        .map(|call| call.id)
+        .filter(|id| !answered.contains(id.as_str()))
+        .collect()
+}
+
+#[tokio::test]
+async fn answers_pending_tool_calls_at_the_iteration_limit() {
+    let provider = ScriptedProvider::default();
+    let mut agent = agent(config("[agent]\nmax_tool_iterations = 2"), &provider).await;
+    agent.register_tool(Arc::new(EchoTool));
+
+    provider.reply(echo_call("call-1"));
+    provider.reply(echo_call("call-2"));
+    provider.reply(Message::assistant("Giving up on tools".to_string()));
+    let reply = agent
+        .respond(Message::user("use tools".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(reply.content, "Giving up on tools");
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 3);
+    let last = &requests[2];
+    assert!(last.tools.is_empty(), "the final request offers no tools");
+    assert!(unanswered_calls(&last.messages).is_empty());
+    let limited = last
+        .messages
+        .iter()
+        .find(|message| message.tool_call_id() == Some("call-2"))
+        .unwrap();
+    assert!(limited.is_error());
+    assert_eq!(limited.message_type, MessageType::ToolResult);
+
+    // The next turn's history has a result for every stored tool call
+    provider.reply(Message::assistant("Second answer".to_string()));
+    let reply = agent
+        .respond(Message::user("and now?".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(reply.content, "Second answer");
+    let requests = provider.requests();
+    let history = &requests[3].messages;
+    assert!(history.iter().any(|m| m.tool_call_id() == Some("call-1")));
+    assert!(unanswered_calls(history).is_empty());
+}
+
+#[tokio::test]
+async fn applies_reloaded_tool_permissions() {
+    let provider = ScriptedProvider::default();
+    let before = config("");
+    let mut agent = agent(before.clone(), &provider).await;
+    agent.register_tool(Arc::new(EchoTool));
+    assert_eq!(agent.tools().len(), 1);
+
+    let after = config("[tools]\ndenied = [\"echo\"]");
+    let changes = ConfigChanges::between(&before, &after).unwrap();
+    assert_eq!(changes.reloadable, ["tools.denied"]);
+    assert!(changes.restart_required.is_empty());
+    agent.apply_config(&after).unwrap();
+    assert!(agent.tools().is_empty());
+
+    provider.reply(echo_call("call-1"));
+    provider.reply(Message::assistant("Without echo".to_string()));
+    let reply = agent
+        .respond(Message::user("use tools".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(reply.content, "Without echo");
+
+    let requests = provider.requests();
+    assert!(requests[0].tools.is_empty(), "denied tools are not offered");
+    let denied = requests[1]
+        .messages
+        .iter()
+        .find(|message| message.tool_call_id() == Some("call-1"))
+        .unwrap();
+    assert!(denied.is_error());
+    assert!(denied.content.contains("tool_not_permitted"));
+}
+
+/// Log output collected in memory
+#[derive(Clone, Default)]
+struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn json_logs_carry_the_session_and_tool_call() {
+    let logs = LogBuffer::default();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_writer({
+            let logs = logs.clone();
+            move || logs.clone()
+        })
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let provider = ScriptedProvider::default();
+    let mut agent = agent(config(""), &provider).await;
+    agent.register_tool(Arc::new(EchoTool));
+    provider.reply(echo_call("call-1"));
+    provider.reply(Message::assistant("Echoed".to_string()));
+    let input = Message::user("use tools".to_string());
+    let (session, message_id) = (input.session().to_string(), input.id.clone());
+    agent.respond(input).await.unwrap();
+
+    let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let events: Vec<serde_json::Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let tool_event = events
+        .iter()
+        .find(|event| event["span"]["name"] == "tool")
+        .expect("an event logged while the tool runs");
+    assert_eq!(
+        tool_event["spans"],
+        json!([
+            { "name": "turn", "session": session, "message_id": message_id },
+            { "name": "tool", "tool": "echo", "call_id": "call-1" },
+        ])
+    );
+}
+
+/// Answers summarization requests with a fixed summary, and anything else
+/// with a plain reply
+struct AnthropicResponder;
+
+impl Respond for AnthropicResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body: serde_json::Value = request.body_json().unwrap();
+        let system = body["system"].as_str().unwrap_or_default();
+        let text = if system.starts_with("You compact conversation history") {
+            "SUMMARY: the user likes otters"
+        } else {
+            "Noted."
+        };
+        ResponseTemplate::new(200).set_body_json(json!({
+            "content": [{ "type": "text", "text": text }],
+            "stop_reason": "end_turn",
+            "usage": { "input_tokens": 10, "output_tokens": 5 }
+        }))
+    }
+}
+
+#[tokio::test]
+async fn sends_the_compaction_summary_to_anthropic() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(AnthropicResponder)
+        .mount(&server)
+        .await;
+
+    let config: Config = toml::from_str(&format!(
+        r#"
+provider = "claude"
+
+[providers.claude]
+type = "anthropic"
+model = "claude-3-haiku-20240307"
+api_key = "sk-ant-test"
+base_url = "{}"
+
+[database]
+backend = "memory"
+
+[agent]
+context_search_results = 0
+
+[agent.compaction]
+enabled = true
+threshold_messages = 4
+keep_recent = 2
+"#,
+        server.uri()
+    ))
+    .unwrap();
+    let agent = SkynetAgent::new(config).await.unwrap();
+
+    for text in [
+        "I like otters",
+        "Remember that",
+        "Thanks",
+        "What do I like?",
+    ] {
+        agent
+            .respond(Message::user(text.to_string()))
+            .await
+            .unwrap();
+    }
+
+    let received = server.received_requests().await.unwrap();
+    let bodies: Vec<serde_json::Value> = received
+        .iter()
+        .map(|request| request.body_json().unwrap())
+        .collect();
+    // Three turns, a summarization request, the last turn, and another
+    // summarization folding the first summary in
+    assert_eq!(bodies.len(), 6);
+    assert!(bodies[3]["system"]
+        .as_str()
+        .unwrap()
+        .starts_with("You compact conversation history"));
+
+    let last = &bodies[4];
+    assert!(last["system"]
+        .as_str()
+        .unwrap()
+        .contains("SUMMARY: the user likes otters"));
+    let history = last["messages"].to_string();
+    assert!(
+        !history.contains("I like otters"),
+        "compacted turns are replaced"
+    );
+    assert!(history.contains("Thanks"));
+    assert!(history.contains("What do I like?"));
+}
@@ -0,0 +1,279 @@
+use futures_util::StreamExt;
+use serde_json::json;
+use skynet_rust::providers::anthropic::AnthropicProvider;
+use skynet_rust::providers::{
+    CompletionRequest, LLMProvider, ProviderError, StreamAccumulator, Usage,
+};
+use skynet_rust::{Message, SkynetError};
+use std::time::Duration;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn provider(base_url: String) -> AnthropicProvider {
+    AnthropicProvider::new("sk-ant-test".to_string())
+        .unwrap()
+        .with_base_url(base_url)
+}
+
+fn error_body(kind: &str, message: &str) -> serde_json::Value {
+    json!({ "type": "error", "error": { "type": kind, "message": message } })
+}
+
+/// Serve `response` for every message request and return the resulting error
+async fn error_for(response: ResponseTemplate) -> ProviderError {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(response)
+        .mount(&server)
+        .await;
+
+    let error = provider(server.uri())
+        .generate(&[Message::user("hi".to_string())])
+        .await
+        .unwrap_err();
+    match error {
+        SkynetError::Provider(error) => error,
+        other => panic!("expected a provider error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn authenticates_with_x_api_key() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header("x-api-key", "sk-ant-test"))
+        .and(header("anthropic-version", "2023-06-01"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "content": [{ "type": "text", "text": "Hello!" }],
+            "stop_reason": "end_turn",
+            "usage": {
+                "input_tokens": 12,
+                "output_tokens": 4,
+                "cache_creation_input_tokens": 100,
+                "cache_read_input_tokens": 50
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let reply = provider(server.uri())
+        .generate(&[Message::user("hi".to_string())])
+        .await
+        .unwrap();
+    assert_eq!(reply.text, "Hello!");
+    assert_eq!(
+        reply.usage,
+        Usage {
+            input_tokens: 12,
+            output_tokens: 4,
+            cache_creation_input_tokens: 100,
+            cache_read_input_tokens: 50,
+        }
+    );
+
+    let received = server.received_requests().await.unwrap();
+    assert!(!received[0]
+        .headers
+        .iter()
+        .any(|(name, _)| name.as_str().eq_ignore_ascii_case("authorization")));
+}
+
+#[tokio::test]
+async fn maps_authentication_errors() {
+    let error = error_for(
+        ResponseTemplate::new(401)
+            .set_body_json(error_body("authentication_error", "invalid x-api-key")),
+    )
+    .await;
+    assert!(
+        matches!(&error, ProviderError::Authentication { message } if message == "invalid x-api-key")
+    );
+}
+
+#[tokio::test]
+async fn maps_rate_limits_with_retry_after() {
+    let error = error_for(
+        ResponseTemplate::new(429)
+            .insert_header("retry-after", "7")
+            .set_body_json(error_body("rate_limit_error", "slow down")),
+    )
+    .await;
+    match error {
+        ProviderError::RateLimited {
+            message,
+            retry_after,
+        } => {
+            assert_eq!(message, "slow down");
+            assert_eq!(retry_after, Some(Duration::from_secs(7)));
+        }
+        other => panic!("expected RateLimited, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn maps_overloaded_errors() {
+    // The API uses 529 here; wiremock cannot emit it, so rely on the error type
+    let error = error_for(
+        ResponseTemplate::new(500).set_body_json(error_body("overloaded_error", "Overloaded")),
+    )
+    .await;
+    assert!(matches!(error, ProviderError::Overloaded { .. }));
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn maps_invalid_requests() {
+    let error = error_for(
+        ResponseTemplate::new(400)
+            .set_body_json(error_body("invalid_request_error", "max_tokens: required")),
+    )
+    .await;
+    assert!(matches!(error, ProviderError::InvalidRequest { .. }));
+    assert!(!error.is_retryable());
+}
+
+#[tokio::test]
+async fn maps_context_overflow() {
+    let error = error_for(ResponseTemplate::new(400).set_body_json(error_body(
+        "invalid_request_error",
+        "prompt is too long: 210000 tokens > 200000 maximum",
+    )))
+    .await;
+    assert!(matches!(error, ProviderError::ContextTooLong { .. }));
+}
+
+#[tokio::test]
+async fn maps_unparseable_error_bodies_by_status() {
+    let error = error_for(ResponseTemplate::new(502).set_body_string("bad gateway")).await;
+    assert!(
+        matches!(&error, ProviderError::Api { status: 502, message } if message == "bad gateway")
+    );
+}
+
+#[tokio::test]
+async fn maps_connection_failures_to_network_errors() {
+    // Grab a free port, then close it so the connection is refused
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+
+    let error = provider(format!("http://{}", address))
+        .generate(&[Message::user("hi".to_string())])
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        SkynetError::Provider(ProviderError::Network(_))
+    ));
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn health_check_lists_models_with_the_api_key() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .and(header("x-api-key", "sk-ant-test"))
+        .and(header("anthropic-version", "2023-06-01"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": [] })))
+        .mount(&server)
+        .await;
+    assert!(provider(server.uri()).health_check().await.unwrap());
+
+    let rejected = AnthropicProvider::new("sk-ant-wrong".to_string())
+        .unwrap()
+        .with_base_url(server.uri());
+    assert!(!rejected.health_check().await.unwrap());
+}
+
+#[tokio::test]
+async fn sends_every_system_message_as_the_system_prompt() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "content": [{ "type": "text", "text": "ok" }],
+            "stop_reason": "end_turn",
+            "usage": { "input_tokens": 1, "output_tokens": 1 }
+        })))
+        .mount(&server)
+        .await;
+
+    provider(server.uri())
+        .generate(&[
+            Message::system("You are SKYNET.".to_string()),
+            Message::system("Relevant earlier messages: ...".to_string()),
+            Message::user("hi".to_string()),
+        ])
+        .await
+        .unwrap();
+
+    let received = server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+    assert_eq!(
+        body["system"],
+        "You are SKYNET.\n\nRelevant earlier messages: ..."
+    );
+    assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn streams_text_followed_by_a_tool_call() {
+    let server = MockServer::start().await;
+    let events = [
+        json!({ "type": "message_start", "message": { "model": "claude-3-5-haiku-20241022", "usage": { "input_tokens": 20, "output_tokens": 1 } } }),
+        json!({ "type": "content_block_start", "index": 0, "content_block": { "type": "text", "text": "" } }),
+        json!({ "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": "Let me check." } }),
+        json!({ "type": "content_block_stop", "index": 0 }),
+        json!({ "type": "content_block_start", "index": 1, "content_block": { "type": "tool_use", "id": "toolu_1", "name": "weather", "input": {} } }),
+        json!({ "type": "content_block_delta", "index": 1, "delta": { "type": "input_json_delta", "partial_json": "{\"city\":" } }),
+        json!({ "type": "content_block_delta", "index": 1, "delta": { "type": "input_json_delta", "partial_json": "\"Oslo\"}" } }),
+        json!({ "type": "content_block_stop", "index": 1 }),
+        json!({ "type": "message_delta", "delta": { "stop_reason": "tool_use" }, "usage": { "output_tokens": 15 } }),
+        json!({ "type": "message_stop" }),
+    ];
+    let body = events
+        .iter()
+        .map(|event| {
+            format!(
+                "event: {}\ndata: {}\n\n",
+                event["type"].as_str().unwrap(),
+                event
+            )
+        })
+        .collect::<String>();
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(body),
+        )
+        .mount(&server)
+        .await;
+
+    let mut stream = provider(server.uri())
+        .generate_stream(&CompletionRequest::new(vec![Message::user(
+            "weather in Oslo?".to_string(),
+        )]))
+        .await
+        .unwrap();
+    let mut accumulator = StreamAccumulator::new();
+    while let Some(event) = stream.next().await {
+        accumulator.push(event.unwrap());
+    }
+    let message = accumulator.into_message("claude-test").unwrap();
+
+    assert_eq!(message.content, "Let me check.");
+    let calls = message.tool_calls();
+    assert_eq!(calls.len(), 1, "text blocks do not become tool calls");
+    assert_eq!(calls[0].id, "toolu_1");
+    assert_eq!(calls[0].name, "weather");
+    assert_eq!(calls[0].arguments, json!({ "city": "Oslo" }));
+    // Attributed to the model the stream reported, not the fallback name
+    assert_eq!(message.model(), Some("claude-3-5-haiku-20241022"));
+    assert_eq!(message.usage().unwrap().output_tokens, 15);
+}
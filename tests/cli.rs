@@ -0,0 +1,208 @@
+//! Smoke tests running the `skynet` binary
+
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::{Output, Stdio};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A scratch directory the binary runs in, isolated from the user's
+/// configuration and environment
+struct Workspace {
+    dir: PathBuf,
+}
+
+impl Workspace {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("skynet-cli-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self { dir }
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.dir.join(file)
+    }
+
+    fn read(&self, file: &str) -> String {
+        std::fs::read_to_string(self.path(file)).unwrap()
+    }
+
+    async fn run(&self, args: &[&str]) -> Output {
+        self.run_with_input(args, "").await
+    }
+
+    async fn run_with_input(&self, args: &[&str], input: &str) -> Output {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_skynet"));
+        command
+            .args(args)
+            .current_dir(&self.dir)
+            .env("HOME", &self.dir)
+            .env("XDG_CONFIG_HOME", &self.dir)
+            .env_remove("ANTHROPIC_API_KEY")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        for (key, _) in std::env::vars_os() {
+            if key.to_string_lossy().starts_with("SKYNET_") {
+                command.env_remove(key);
+            }
+        }
+
+        let mut child = command.spawn().unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(input.as_bytes()).await.unwrap();
+        drop(stdin);
+        child.wait_with_output().await.unwrap()
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", stderr(output));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[tokio::test]
+async fn create_config_writes_a_default_file_once() {
+    let workspace = Workspace::new("create-config");
+
+    stdout(&workspace.run(&["--create-config", "skynet.toml"]).await);
+    let written = workspace.read("skynet.toml");
+    assert!(written.contains("temperature = 0.7\n"), "{}", written);
+    assert!(
+        written.contains(r#"anthropic_api_key = "env:ANTHROPIC_API_KEY""#),
+        "{}",
+        written
+    );
+
+    let output = workspace.run(&["--create-config", "skynet.toml"]).await;
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("already exists"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[tokio::test]
+async fn config_init_overwrites_only_with_force() {
+    let workspace = Workspace::new("config-init");
+    std::fs::write(workspace.path("skynet.toml"), "# mine\n").unwrap();
+
+    let output = workspace.run(&["config", "init"]).await;
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("pass --force"),
+        "{}",
+        stderr(&output)
+    );
+    assert_eq!(workspace.read("skynet.toml"), "# mine\n");
+
+    stdout(&workspace.run(&["config", "init", "--force"]).await);
+    assert!(workspace.read("skynet.toml").contains("[agent]"));
+
+    // Without a path, init writes to the file given by --config
+    stdout(
+        &workspace
+            .run(&["--config", "other.toml", "config", "init"])
+            .await,
+    );
+    assert!(workspace.path("other.toml").exists());
+}
+
+#[tokio::test]
+async fn ask_reads_stdin_and_memory_exports_and_prunes_the_exchange() {
+    let server = MockServer::start().await;
+    let body = [
+        r#"{"model":"m","choices":[{"delta":{"role":"assistant","content":"Hel"},"finish_reason":null}]}"#,
+        r#"{"choices":[{"delta":{"content":"lo"},"finish_reason":"stop"}]}"#,
+        r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":2}}"#,
+        "[DONE]",
+    ]
+    .iter()
+    .map(|data| format!("data: {}\n\n", data))
+    .collect::<String>();
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(serde_json::json!({ "stream": true })))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(body),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let workspace = Workspace::new("ask");
+    std::fs::write(
+        workspace.path("skynet.toml"),
+        format!(
+            "provider = \"mock\"\n\
+             [providers.mock]\n\
+             type = \"openai\"\n\
+             api_key = \"k\"\n\
+             model = \"m\"\n\
+             base_url = \"{}/v1\"\n",
+            server.uri()
+        ),
+    )
+    .unwrap();
+
+    let answer = stdout(&workspace.run_with_input(&["ask"], "What is up?\n").await);
+    assert!(answer.contains("Hello"), "{}", answer);
+    let request = &server.received_requests().await.unwrap()[0];
+    assert!(String::from_utf8_lossy(&request.body).contains("What is up?"));
+
+    let export = stdout(&workspace.run(&["memory", "export"]).await);
+    let export: Value = serde_json::from_str(&export).unwrap();
+    let contents: Vec<&str> = export[0]["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message["content"].as_str().unwrap())
+        .collect();
+    assert_eq!(contents, ["What is up?", "Hello"]);
+
+    stdout(
+        &workspace
+            .run(&["memory", "export", "--output", "export.json"])
+            .await,
+    );
+    let saved: Value = serde_json::from_str(&workspace.read("export.json")).unwrap();
+    assert_eq!(saved, export);
+
+    let dry_run = stdout(
+        &workspace
+            .run(&["memory", "prune", "--older-than", "0", "--dry-run"])
+            .await,
+    );
+    assert!(
+        dry_run.starts_with("Would delete 2 messages"),
+        "{}",
+        dry_run
+    );
+    let pruned = stdout(
+        &workspace
+            .run(&["memory", "prune", "--older-than", "0"])
+            .await,
+    );
+    assert!(pruned.starts_with("Deleted 2 messages"), "{}", pruned);
+
+    let export = stdout(&workspace.run(&["memory", "export"]).await);
+    let export: Value = serde_json::from_str(&export).unwrap();
+    assert_eq!(export[0]["messages"], Value::Array(Vec::new()));
+    assert!(workspace.path("skynet.db").exists());
+}
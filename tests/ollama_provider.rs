@@ -0,0 +1,176 @@
+use futures_util::StreamExt;
+use serde_json::json;
+use skynet_rust::providers::ollama::OllamaProvider;
+use skynet_rust::providers::{
+    CompletionRequest, LLMProvider, ProviderError, StreamAccumulator, StreamEvent, Usage,
+};
+use skynet_rust::{Message, SkynetError};
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn provider(server: &MockServer, model: &str) -> OllamaProvider {
+    OllamaProvider::new(model.to_string())
+        .unwrap()
+        .with_base_url(server.uri())
+}
+
+/// Serve `/api/tags` listing the given model names
+async fn serve_tags(server: &MockServer, names: &[&str]) {
+    let models: Vec<serde_json::Value> = names
+        .iter()
+        .map(|name| json!({ "name": name, "size": 42, "digest": "sha256:abc" }))
+        .collect();
+    Mock::given(method("GET"))
+        .and(path("/api/tags"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "models": models })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn lists_installed_models() {
+    let server = MockServer::start().await;
+    serve_tags(&server, &["llama3:latest", "mistral:7b"]).await;
+
+    let models = provider(&server, "llama3").list_models().await.unwrap();
+    let names: Vec<&str> = models.iter().map(|model| model.name.as_str()).collect();
+    assert_eq!(names, ["llama3:latest", "mistral:7b"]);
+    assert_eq!(models[0].size, 42);
+    assert_eq!(models[0].digest, "sha256:abc");
+}
+
+#[tokio::test]
+async fn healthy_only_when_the_model_is_installed() {
+    let server = MockServer::start().await;
+    serve_tags(&server, &["llama3:latest", "mistral:7b"]).await;
+
+    // A bare name matches its `:latest` tag, an explicit tag only itself
+    assert!(provider(&server, "llama3").health_check().await.unwrap());
+    assert!(provider(&server, "llama3:latest")
+        .health_check()
+        .await
+        .unwrap());
+    assert!(provider(&server, "mistral:7b")
+        .health_check()
+        .await
+        .unwrap());
+    assert!(!provider(&server, "mistral").health_check().await.unwrap());
+    assert!(!provider(&server, "phi3").health_check().await.unwrap());
+}
+
+#[tokio::test]
+async fn health_check_fails_when_the_server_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/tags"))
+        .respond_with(ResponseTemplate::new(500).set_body_json(json!({ "error": "boom" })))
+        .mount(&server)
+        .await;
+
+    let error = provider(&server, "llama3")
+        .health_check()
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        SkynetError::Provider(ProviderError::Api { status: 500, ref message }) if message == "boom"
+    ));
+}
+
+#[tokio::test]
+async fn pulls_a_model_and_reports_failures() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/pull"))
+        .and(body_partial_json(
+            json!({ "model": "llama3", "stream": false }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "success" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/pull"))
+        .and(body_partial_json(json!({ "model": "nope" })))
+        .respond_with(
+            ResponseTemplate::new(500)
+                .set_body_json(json!({ "error": "pull model manifest: file does not exist" })),
+        )
+        .mount(&server)
+        .await;
+
+    let ollama = provider(&server, "llama3");
+    ollama.pull_model("llama3").await.unwrap();
+    let error = ollama.pull_model("nope").await.unwrap_err().to_string();
+    assert!(error.contains("file does not exist"), "{}", error);
+}
+
+#[tokio::test]
+async fn streams_an_ndjson_reply() {
+    let server = MockServer::start().await;
+    let lines = [
+        json!({ "model": "llama3:latest", "message": { "role": "assistant", "content": "Hel" }, "done": false }),
+        json!({ "model": "llama3:latest", "message": { "role": "assistant", "content": "lo" }, "done": false }),
+        json!({
+            "model": "llama3:latest",
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{ "function": { "name": "weather", "arguments": { "city": "Oslo" } } }]
+            },
+            "done": false
+        }),
+        json!({
+            "model": "llama3:latest",
+            "message": { "role": "assistant", "content": "" },
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 12,
+            "eval_count": 7
+        }),
+    ];
+    let body: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .and(body_partial_json(
+            json!({ "model": "llama3", "stream": true }),
+        ))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "application/x-ndjson")
+                .set_body_string(body),
+        )
+        .mount(&server)
+        .await;
+
+    let stream = provider(&server, "llama3")
+        .generate_stream(&CompletionRequest::new(vec![Message::user(
+            "hi".to_string(),
+        )]))
+        .await
+        .unwrap();
+    let events: Vec<StreamEvent> = stream.map(|event| event.unwrap()).collect().await;
+    assert_eq!(
+        events.last(),
+        Some(&StreamEvent::Done {
+            stop_reason: Some("stop".to_string()),
+            usage: Usage {
+                input_tokens: 12,
+                output_tokens: 7,
+                ..Default::default()
+            },
+            provider: None,
+            model: Some("llama3:latest".to_string()),
+        })
+    );
+
+    let mut accumulator = StreamAccumulator::new();
+    events.into_iter().for_each(|event| accumulator.push(event));
+    let message = accumulator.into_message("llama3").unwrap();
+    assert_eq!(message.content, "Hello");
+    let calls = message.tool_calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].name, "weather");
+    assert_eq!(calls[0].arguments, json!({ "city": "Oslo" }));
+    assert_eq!(message.model(), Some("llama3:latest"));
+}
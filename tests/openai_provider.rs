@@ -0,0 +1,227 @@
+use futures_util::StreamExt;
+use serde_json::json;
+use skynet_rust::providers::openai::OpenAIProvider;
+use skynet_rust::providers::{CompletionRequest, LLMProvider, StreamEvent, ToolDefinition, Usage};
+use skynet_rust::{Message, MessageType, ToolCall};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+fn provider(server: &MockServer, api_key: &str) -> OpenAIProvider {
+    OpenAIProvider::new(api_key.to_string(), "test-model".to_string())
+        .unwrap()
+        .with_base_url(format!("{}/v1", server.uri()))
+}
+
+fn request_body(request: &Request) -> serde_json::Value {
+    serde_json::from_slice(&request.body).unwrap()
+}
+
+#[tokio::test]
+async fn maps_roles_and_tool_call_linkage() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(header("authorization", "Bearer sk-test"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{
+                "message": { "role": "assistant", "content": "It is sunny." },
+                "finish_reason": "stop"
+            }],
+            "model": "test-model-0613",
+            "usage": {
+                "prompt_tokens": 30,
+                "completion_tokens": 5,
+                "prompt_tokens_details": { "cached_tokens": 20 }
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let call = ToolCall {
+        id: "call_1".to_string(),
+        name: "weather".to_string(),
+        arguments: json!({ "city": "Oslo" }),
+    };
+    let messages = vec![
+        Message::system("Be brief.".to_string()),
+        Message::user("Weather in Oslo?".to_string()),
+        Message::tool_call(String::new(), vec![call]),
+        Message::tool_result("call_1".to_string(), "sunny".to_string(), false),
+    ];
+    let tool = ToolDefinition {
+        name: "weather".to_string(),
+        description: "Look up the weather".to_string(),
+        input_schema: json!({ "type": "object" }),
+    };
+
+    let response = provider(&server, "sk-test")
+        .complete(&CompletionRequest::new(messages).with_tools(vec![tool]))
+        .await
+        .unwrap();
+    assert_eq!(response.content, "It is sunny.");
+    assert_eq!(response.model(), Some("test-model-0613"));
+    assert_eq!(
+        response.usage(),
+        Some(Usage {
+            input_tokens: 10,
+            output_tokens: 5,
+            cache_read_input_tokens: 20,
+            ..Default::default()
+        })
+    );
+
+    let body = request_body(&server.received_requests().await.unwrap()[0]);
+    assert_eq!(body["model"], "test-model");
+    let sent = body["messages"].as_array().unwrap();
+    assert_eq!(sent[0], json!({ "role": "system", "content": "Be brief." }));
+    assert_eq!(sent[1]["role"], "user");
+    assert_eq!(sent[2]["role"], "assistant");
+    assert_eq!(sent[2]["content"], serde_json::Value::Null);
+    assert_eq!(sent[2]["tool_calls"][0]["id"], "call_1");
+    assert_eq!(sent[2]["tool_calls"][0]["type"], "function");
+    assert_eq!(
+        sent[2]["tool_calls"][0]["function"]["arguments"],
+        r#"{"city":"Oslo"}"#
+    );
+    assert_eq!(
+        sent[3],
+        json!({ "role": "tool", "content": "sunny", "tool_call_id": "call_1" })
+    );
+    assert_eq!(body["tools"][0]["function"]["name"], "weather");
+}
+
+#[tokio::test]
+async fn parses_tool_calls_from_response() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_9",
+                        "type": "function",
+                        "function": { "name": "weather", "arguments": "{\"city\":\"Rome\"}" }
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        })))
+        .mount(&server)
+        .await;
+
+    let response = provider(&server, "")
+        .complete(&CompletionRequest::new(vec![Message::user(
+            "hi".to_string(),
+        )]))
+        .await
+        .unwrap();
+
+    assert_eq!(response.message_type, MessageType::ToolCall);
+    assert_eq!(
+        response.tool_calls(),
+        vec![ToolCall {
+            id: "call_9".to_string(),
+            name: "weather".to_string(),
+            arguments: json!({ "city": "Rome" }),
+        }]
+    );
+
+    // Local servers usually run without a key; no auth header should be sent
+    let received = server.received_requests().await.unwrap();
+    assert!(!received[0]
+        .headers
+        .iter()
+        .any(|(name, _)| name.as_str().eq_ignore_ascii_case("authorization")));
+}
+
+#[tokio::test]
+async fn streams_text_tool_calls_and_usage() {
+    let server = MockServer::start().await;
+    let body = [
+        r#"{"model":"gpt-4o-2024-08-06","choices":[{"delta":{"role":"assistant","content":"Hel"},"finish_reason":null}]}"#,
+        r#"{"choices":[{"delta":{"content":"lo"},"finish_reason":null}]}"#,
+        r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"weather","arguments":""}}]},"finish_reason":null}]}"#,
+        r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"city\":"}}]},"finish_reason":null}]}"#,
+        r#"{"choices":[{"delta":{},"finish_reason":"tool_calls"}]}"#,
+        r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":7}}"#,
+        "[DONE]",
+    ]
+    .iter()
+    .map(|data| format!("data: {}\n\n", data))
+    .collect::<String>();
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(body),
+        )
+        .mount(&server)
+        .await;
+
+    let stream = provider(&server, "sk-test")
+        .generate_stream(&CompletionRequest::new(vec![Message::user(
+            "hi".to_string(),
+        )]))
+        .await
+        .unwrap();
+    let events: Vec<StreamEvent> = stream.map(|event| event.unwrap()).collect().await;
+
+    assert_eq!(
+        events,
+        vec![
+            StreamEvent::TextDelta("Hel".to_string()),
+            StreamEvent::TextDelta("lo".to_string()),
+            StreamEvent::ToolCallStart {
+                index: 0,
+                id: "call_1".to_string(),
+                name: "weather".to_string(),
+            },
+            StreamEvent::ToolCallDelta {
+                index: 0,
+                partial_json: "{\"city\":".to_string(),
+            },
+            StreamEvent::Done {
+                stop_reason: Some("tool_calls".to_string()),
+                usage: Usage {
+                    input_tokens: 12,
+                    output_tokens: 7,
+                    ..Default::default()
+                },
+                provider: None,
+                model: Some("gpt-4o-2024-08-06".to_string()),
+            },
+        ]
+    );
+
+    let sent = request_body(&server.received_requests().await.unwrap()[0]);
+    assert_eq!(sent["stream"], true);
+}
+
+#[tokio::test]
+async fn reports_http_errors_and_health() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("boom"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": [] })))
+        .mount(&server)
+        .await;
+
+    let provider = provider(&server, "sk-test");
+    let error = provider
+        .generate(&[Message::user("hi".to_string())])
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("boom"));
+    assert!(provider.health_check().await.unwrap());
+}